@@ -0,0 +1,78 @@
+//! Compares counting descriptors with `getdents64` to listing them with a
+//! `readlink` per descriptor, on a process with many open descriptors.
+//!
+//! Run with `cargo bench --bench list_total`. 100k descriptors require a
+//! high enough limit, e.g. `ulimit -n 200000`. The number of descriptors can
+//! be changed with `FSHC_BENCH_DESCRIPTORS`.
+
+#[cfg(target_os = "linux")]
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use std::fs::File;
+    use std::hint::black_box;
+    use std::time::{Duration, Instant};
+
+    use fshc::{QueryOptions, count_descriptors};
+    use procfs::process::Process;
+
+    const ITERATIONS: u32 = 20;
+
+    fn measure<T>(name: &str, mut f: impl FnMut() -> T) {
+        black_box(f());
+        let started = Instant::now();
+        for _ in 0..ITERATIONS {
+            black_box(f());
+        }
+        let per_call: Duration = started.elapsed() / ITERATIONS;
+        println!("{name:<40} {per_call:>12.2?} per call");
+    }
+
+    let pid = std::process::id();
+    let wanted: u64 = std::env::var("FSHC_BENCH_DESCRIPTORS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(100_000);
+    let options = QueryOptions {
+        limit: true,
+        ..Default::default()
+    };
+    let soft_limit = count_descriptors(pid, &options)?
+        .descriptor_limit
+        .and_then(|limit| limit.soft)
+        .unwrap_or(u64::MAX);
+    // leaves some room for the descriptors of the benchmark itself
+    let descriptors = wanted.min(soft_limit.saturating_sub(64));
+    if descriptors < wanted {
+        println!(
+            "opening {descriptors} descriptors, raise the limit with `ulimit -n` for {wanted}"
+        );
+    }
+
+    let files = (0..descriptors)
+        .map(|_| File::open("/dev/null"))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let only_total = QueryOptions {
+        only_total: true,
+        ..Default::default()
+    };
+    let total = count_descriptors(pid, &only_total)?.total_descriptors;
+    println!("process {pid} has {total} open descriptors\n");
+
+    measure("readlink every descriptor (procfs)", || {
+        Process::new(pid as i32).map(|proc| proc.fd().map(|fds| fds.flatten().count()))
+    });
+    measure("list_by_type", || {
+        count_descriptors(pid, &QueryOptions::default())
+    });
+    measure("list_total (getdents64)", || {
+        count_descriptors(pid, &only_total)
+    });
+
+    drop(files);
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn main() {
+    println!("the list_total benchmark is only supported on Linux");
+}
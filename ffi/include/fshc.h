@@ -0,0 +1,96 @@
+#ifndef FSHC_H
+#define FSHC_H
+
+/* Generated by cbindgen from ffi/src/lib.rs, do not edit. Regenerate by running `cargo test -p fshc-ffi` with FSHC_UPDATE_HEADER=1 */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Only count descriptors, without telling sockets and files apart.
+ */
+#define FSHC_ONLY_TOTAL 1
+
+/**
+ * Also read the descriptor limits (RLIMIT_NOFILE) of the process.
+ */
+#define FSHC_LIMIT 2
+
+#define FSHC_OK 0
+
+/**
+ * The pid is not within the supported range
+ */
+#define FSHC_PID_OUT_OF_RANGE 1
+
+/**
+ * There is no such process, or it exited while being inspected
+ */
+#define FSHC_NOT_FOUND 2
+
+#define FSHC_INVALID_INPUT 3
+
+/**
+ * The caller lacks the privileges to inspect the process
+ */
+#define FSHC_PERMISSION_DENIED 4
+
+#define FSHC_IO_ERROR 5
+
+/**
+ * The requested report is not supported on this platform
+ */
+#define FSHC_UNSUPPORTED 6
+
+/**
+ * An operating system API failed
+ */
+#define FSHC_OS_ERROR 7
+
+#define FSHC_OTHER 8
+
+/**
+ * A null `stats` pointer or unknown flags were passed
+ */
+#define FSHC_INVALID_ARGUMENT 9
+
+/**
+ * Descriptor counts of a process. Counts that were not collected, either
+ * because of `FSHC_ONLY_TOTAL` or because the platform does not provide them,
+ * are `-1`. So are descriptor limits that were not asked for with `FSHC_LIMIT`,
+ * are unlimited or could not be read.
+ */
+typedef struct FshcStats {
+  uint32_t pid;
+  uint32_t total_descriptors;
+  int64_t socket_descriptors;
+  int64_t file_descriptors;
+  int64_t descriptor_limit_soft;
+  int64_t descriptor_limit_hard;
+} FshcStats;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Counts the descriptors of process `pid` and stores them in `stats`.
+ *
+ * `flags` is a combination of `FSHC_*` flags, or 0. Returns `FSHC_OK`
+ * on success, otherwise one of the `FSHC_*` error codes, in which case
+ * `stats` is left untouched.
+ *
+ * # Safety
+ *
+ * `stats` must be null or point to memory that is valid for writing an `FshcStats`.
+ */
+int32_t fshc_count(uint32_t pid, uint32_t flags, struct FshcStats *stats);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* FSHC_H */
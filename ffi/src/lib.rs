@@ -0,0 +1,95 @@
+//! A C ABI for fshc, e.g. for Erlang NIFs or Python's ctypes.
+//! See `include/fshc.h` for the generated C header.
+
+use fshc::{FailureCode, QueryOptions};
+
+/// Only count descriptors, without telling sockets and files apart.
+pub const FSHC_ONLY_TOTAL: u32 = 1;
+/// Also read the descriptor limits (RLIMIT_NOFILE) of the process.
+pub const FSHC_LIMIT: u32 = 2;
+
+const ALL_FLAGS: u32 = FSHC_ONLY_TOTAL | FSHC_LIMIT;
+
+pub const FSHC_OK: i32 = 0;
+/// The pid is not within the supported range
+pub const FSHC_PID_OUT_OF_RANGE: i32 = 1;
+/// There is no such process, or it exited while being inspected
+pub const FSHC_NOT_FOUND: i32 = 2;
+pub const FSHC_INVALID_INPUT: i32 = 3;
+/// The caller lacks the privileges to inspect the process
+pub const FSHC_PERMISSION_DENIED: i32 = 4;
+pub const FSHC_IO_ERROR: i32 = 5;
+/// The requested report is not supported on this platform
+pub const FSHC_UNSUPPORTED: i32 = 6;
+/// An operating system API failed
+pub const FSHC_OS_ERROR: i32 = 7;
+pub const FSHC_OTHER: i32 = 8;
+/// A null `stats` pointer or unknown flags were passed
+pub const FSHC_INVALID_ARGUMENT: i32 = 9;
+
+/// Descriptor counts of a process. Counts that were not collected, either
+/// because of `FSHC_ONLY_TOTAL` or because the platform does not provide them,
+/// are `-1`. So are descriptor limits that were not asked for with `FSHC_LIMIT`,
+/// are unlimited or could not be read.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FshcStats {
+    pub pid: u32,
+    pub total_descriptors: u32,
+    pub socket_descriptors: i64,
+    pub file_descriptors: i64,
+    pub descriptor_limit_soft: i64,
+    pub descriptor_limit_hard: i64,
+}
+
+/// Counts the descriptors of process `pid` and stores them in `stats`.
+///
+/// `flags` is a combination of `FSHC_*` flags, or 0. Returns `FSHC_OK`
+/// on success, otherwise one of the `FSHC_*` error codes, in which case
+/// `stats` is left untouched.
+///
+/// # Safety
+///
+/// `stats` must be null or point to memory that is valid for writing an `FshcStats`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn fshc_count(pid: u32, flags: u32, stats: *mut FshcStats) -> i32 {
+    if stats.is_null() || flags & !ALL_FLAGS != 0 {
+        return FSHC_INVALID_ARGUMENT;
+    }
+
+    let options = QueryOptions {
+        only_total: flags & FSHC_ONLY_TOTAL != 0,
+        limit: flags & FSHC_LIMIT != 0,
+        ..Default::default()
+    };
+    match fshc::count_descriptors(pid, &options) {
+        Ok(result) => {
+            let limit = result.descriptor_limit;
+            let counted = FshcStats {
+                pid: result.pid,
+                total_descriptors: result.total_descriptors,
+                socket_descriptors: or_missing(result.socket_descriptors.map(u64::from)),
+                file_descriptors: or_missing(result.file_descriptors.map(u64::from)),
+                descriptor_limit_soft: or_missing(limit.and_then(|l| l.soft)),
+                descriptor_limit_hard: or_missing(limit.and_then(|l| l.hard)),
+            };
+            // SAFETY: the caller guarantees that a non-null `stats` is valid for writes
+            unsafe { stats.write(counted) };
+            FSHC_OK
+        }
+        Err(err) => match err.code() {
+            FailureCode::PidOutOfRange => FSHC_PID_OUT_OF_RANGE,
+            FailureCode::NotFound => FSHC_NOT_FOUND,
+            FailureCode::InvalidInput => FSHC_INVALID_INPUT,
+            FailureCode::PermissionDenied => FSHC_PERMISSION_DENIED,
+            FailureCode::IoError => FSHC_IO_ERROR,
+            FailureCode::Unsupported => FSHC_UNSUPPORTED,
+            FailureCode::OsError => FSHC_OS_ERROR,
+            FailureCode::TimedOut | FailureCode::InvalidRequest | FailureCode::Other => FSHC_OTHER,
+        },
+    }
+}
+
+fn or_missing(value: Option<u64>) -> i64 {
+    value.and_then(|n| i64::try_from(n).ok()).unwrap_or(-1)
+}
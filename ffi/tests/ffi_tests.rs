@@ -0,0 +1,98 @@
+// Copyright (C) 2024-2025 Broadcom. All Rights Reserved.
+// The term "Broadcom" refers to Broadcom Inc. and/or its subsidiaries.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use fshc_ffi::*;
+use std::path::Path;
+use std::ptr;
+
+#[test]
+fn count_descriptors_of_current_process() {
+    let mut stats = FshcStats {
+        pid: 0,
+        total_descriptors: 0,
+        socket_descriptors: 0,
+        file_descriptors: 0,
+        descriptor_limit_soft: 0,
+        descriptor_limit_hard: 0,
+    };
+
+    let code = unsafe { fshc_count(std::process::id(), 0, &mut stats) };
+
+    assert_eq!(code, FSHC_OK);
+    assert_eq!(stats.pid, std::process::id());
+    assert!(stats.total_descriptors >= 1);
+    assert_eq!(stats.descriptor_limit_soft, -1);
+
+    let flags = FSHC_ONLY_TOTAL | FSHC_LIMIT;
+    let code = unsafe { fshc_count(std::process::id(), flags, &mut stats) };
+
+    assert_eq!(code, FSHC_OK);
+    assert_eq!(stats.socket_descriptors, -1);
+    assert_eq!(stats.file_descriptors, -1);
+    if cfg!(target_os = "linux") {
+        assert!(stats.descriptor_limit_soft > 0);
+    }
+}
+
+#[test]
+fn fail_with_error_codes() {
+    let mut stats = FshcStats {
+        pid: 7,
+        total_descriptors: 7,
+        socket_descriptors: 7,
+        file_descriptors: 7,
+        descriptor_limit_soft: 7,
+        descriptor_limit_hard: 7,
+    };
+    let untouched = stats;
+
+    assert_eq!(
+        unsafe { fshc_count(0, 0, &mut stats) },
+        FSHC_PID_OUT_OF_RANGE
+    );
+    assert_eq!(
+        unsafe { fshc_count(std::process::id(), 1 << 31, &mut stats) },
+        FSHC_INVALID_ARGUMENT
+    );
+    assert_eq!(
+        unsafe { fshc_count(std::process::id(), 0, ptr::null_mut()) },
+        FSHC_INVALID_ARGUMENT
+    );
+    assert_eq!(stats, untouched);
+}
+
+#[test]
+fn header_is_up_to_date() {
+    let crate_dir = env!("CARGO_MANIFEST_DIR");
+    let config = cbindgen::Config::from_file(Path::new(crate_dir).join("cbindgen.toml"))
+        .expect("invalid cbindgen.toml");
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_crate(crate_dir)
+        .with_config(config)
+        .generate()
+        .expect("failed to generate the C header")
+        .write(&mut generated);
+
+    let header_path = Path::new(crate_dir).join("include/fshc.h");
+    if std::env::var_os("FSHC_UPDATE_HEADER").is_some() {
+        std::fs::write(&header_path, &generated).expect("failed to write the C header");
+    }
+    let header = std::fs::read(&header_path).unwrap_or_default();
+    assert!(
+        header == generated,
+        "include/fshc.h is out of date, run `FSHC_UPDATE_HEADER=1 cargo test -p fshc-ffi`"
+    );
+}
@@ -0,0 +1,48 @@
+use std::io::{self, BufRead, Write};
+
+use sysexits::ExitCode;
+
+use fshc::outcome::*;
+
+use crate::format::OutputFormat;
+use crate::request::Request;
+
+/// Reads one JSON request per line and answers each of them with a line
+/// holding either the stats or the failure, in the order the requests came in.
+/// Blank lines are ignored. The session ends when standard input is closed.
+pub fn run() -> ExitCode {
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    let mut line = Vec::new();
+
+    loop {
+        line.clear();
+        // Lines are read as bytes, a line that is not UTF-8 fails to decode like any other bad request
+        match stdin.read_until(b'\n', &mut line) {
+            Ok(0) => return ExitCode::Ok,
+            Ok(_) => {}
+            Err(err) => return fail("Failed to read a request", &err),
+        }
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_slice(&line)
+            .map_err(|err| Failure::invalid_request(err.to_string()))
+            .and_then(Request::from_json)
+        {
+            Ok(request) => request.respond(OutputFormat::Json),
+            Err(failure) => OutputFormat::Json.render_failure(&failure),
+        };
+
+        // callers wait for the answer before sending the next request
+        if let Err(err) = stdout.write_all(&response).and_then(|_| stdout.flush()) {
+            return fail("Failed to write a response", &err);
+        }
+    }
+}
+
+fn fail(message: &str, err: &io::Error) -> ExitCode {
+    let failure = Failure::io(message.to_string(), err);
+    crate::exit(OutputFormat::Json.render_failure(&failure), err.exit_code())
+}
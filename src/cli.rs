@@ -0,0 +1,199 @@
+use clap::{Args, Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use fshc::QueryOptions;
+
+use crate::format::OutputFormat;
+use crate::time::{parse_duration, parse_interval};
+use crate::wait::{Condition, parse_condition};
+
+#[derive(Parser, Debug)]
+#[command(
+    version = clap::crate_version!(),
+    about = "File and socket handle counter",
+    long_about = None,
+    after_help = "GitHub: https://github.com/rabbitmq/fshc",
+    subcommand_negates_reqs = true,
+    args_conflicts_with_subcommands = true
+)]
+pub struct CliArgs {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// The process to inspect. Can be repeated or comma-separated to inspect several processes
+    #[arg(short, long, required = true, value_delimiter = ',')]
+    pub pid: Vec<u32>,
+    #[command(flatten)]
+    pub query: QueryArgs,
+    /// Output format [default: table when standard output is a terminal, json otherwise]
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+    /// Keep sampling at this interval (e.g. 500ms, 10s, 5m) until interrupted
+    #[arg(long, value_parser = parse_interval)]
+    pub interval: Option<Duration>,
+    /// Stop after this many samples
+    #[arg(long, requires = "interval")]
+    pub count: Option<u64>,
+    /// Print how every process behaved when sampling stops or is interrupted, instead of every sample
+    #[arg(long, requires = "interval", conflicts_with = "statsd_addr")]
+    pub summary: bool,
+    /// Query up to this many processes at the same time
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: u16,
+    /// Give up on a process after this long (e.g. 500ms, 10s), e.g. when its /proc entries hang
+    #[arg(long, value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+    /// Send StatsD gauges to this address over UDP instead of printing them
+    #[arg(long)]
+    pub statsd_addr: Option<SocketAddr>,
+}
+
+/// The command line flags of [`QueryOptions`].
+#[derive(Args, Debug, Clone)]
+pub struct QueryArgs {
+    /// Only count descriptors, without telling sockets and files apart
+    #[arg(long)]
+    pub only_total: bool,
+    /// Report the soft and hard descriptor limits (RLIMIT_NOFILE) of the process
+    #[arg(long)]
+    pub limit: bool,
+    /// Group Unix domain sockets by bound path, peer and socket type (Linux only)
+    #[arg(long, conflicts_with = "only_total")]
+    pub unix_sockets: bool,
+    /// Summarize access modes, O_NONBLOCK and missing O_CLOEXEC (Linux only)
+    #[arg(long, conflicts_with = "only_total")]
+    pub fd_flags: bool,
+    /// Count epoll and inotify instances and their registered watches (Linux only)
+    #[arg(long, conflicts_with = "only_total")]
+    pub watches: bool,
+    /// List POSIX, flock and OFD locks held on open files (Linux only)
+    #[arg(long, conflicts_with = "only_total")]
+    pub locks: bool,
+    /// List memory-mapped files, including the ones without a descriptor (Linux only)
+    #[arg(long, conflicts_with = "only_total")]
+    pub mappings: bool,
+}
+
+impl From<&QueryArgs> for QueryOptions {
+    fn from(args: &QueryArgs) -> Self {
+        QueryOptions {
+            only_total: args.only_total,
+            limit: args.limit,
+            unix_sockets: args.unix_sockets,
+            fd_flags: args.fd_flags,
+            watches: args.watches,
+            locks: args.locks,
+            mappings: args.mappings,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Serve descriptor stats over HTTP, recomputed on every scrape of /metrics
+    Serve(ServeArgs),
+    /// Answer {packet, 4} framed requests on standard input until it is closed, e.g. as an Erlang port
+    Port(PortArgs),
+    /// Answer one JSON request per line on standard input with one JSON document per line
+    Batch,
+    /// Print the JSON Schema of the JSON output
+    Schema,
+    /// Save every descriptor of a process, to compare it with `fshc diff` later (Linux only)
+    Snapshot(SnapshotArgs),
+    /// Report the descriptors opened and closed between two snapshots
+    Diff(DiffArgs),
+    /// Keep sampling a process into a recording of a fixed size that overwrites the oldest samples
+    Record(RecordArgs),
+    /// Print or summarize the samples of a recording
+    Replay(ReplayArgs),
+    /// Wait until the descriptors of a process meet a condition, e.g. until its connections drain
+    Wait(WaitArgs),
+}
+
+#[derive(Args, Debug)]
+pub struct ServeArgs {
+    /// The address to listen on
+    #[arg(long, default_value = "127.0.0.1:9837")]
+    pub listen: SocketAddr,
+    #[arg(short, long)]
+    pub pid: u32,
+    #[command(flatten)]
+    pub query: QueryArgs,
+}
+
+#[derive(Args, Debug)]
+pub struct PortArgs {
+    /// Response format
+    #[arg(long, value_enum, default_value = "etf")]
+    pub format: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+pub struct SnapshotArgs {
+    #[arg(short, long)]
+    pub pid: u32,
+    /// The file to save the snapshot to [default: standard output]
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// The earlier snapshot
+    pub before: PathBuf,
+    /// The later snapshot
+    pub after: PathBuf,
+    /// Output format, json or table [default: table when standard output is a terminal, json otherwise]
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+}
+
+#[derive(Args, Debug)]
+pub struct RecordArgs {
+    #[arg(short, long)]
+    pub pid: u32,
+    /// How often to sample the process (e.g. 500ms, 10s, 5m)
+    #[arg(long, default_value = "10s", value_parser = parse_interval)]
+    pub interval: Duration,
+    /// The recording to append to. It is created if it does not exist
+    #[arg(long)]
+    pub file: PathBuf,
+    /// How many samples a new recording keeps before overwriting the oldest ones
+    #[arg(long, default_value_t = 8640, value_parser = clap::value_parser!(u32).range(1..))]
+    pub capacity: u32,
+    /// Stop after this many samples
+    #[arg(long)]
+    pub count: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub struct ReplayArgs {
+    /// The recording to read
+    #[arg(long)]
+    pub file: PathBuf,
+    /// Print the minimum, maximum, mean and time of the peak of every category instead of the samples
+    #[arg(long)]
+    pub summary: bool,
+    /// Output format, json or table [default: table when standard output is a terminal, json otherwise]
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+}
+
+#[derive(Args, Debug)]
+pub struct WaitArgs {
+    #[arg(short, long)]
+    pub pid: u32,
+    /// A condition on total, sockets or files, e.g. sockets<100 or total>=10. Can be repeated, all conditions have to hold
+    #[arg(long, required = true, value_parser = parse_condition)]
+    pub until: Vec<Condition>,
+    /// How often to check the conditions (e.g. 500ms, 10s)
+    #[arg(long, default_value = "1s", value_parser = parse_interval)]
+    pub interval: Duration,
+    /// Give up after this long (e.g. 30s, 5m) [default: wait indefinitely]
+    #[arg(long, value_parser = parse_duration)]
+    pub timeout: Option<Duration>,
+    /// Format of the stats printed once the conditions hold [default: table when standard output is a terminal, json otherwise]
+    #[arg(long, value_enum)]
+    pub format: Option<OutputFormat>,
+}
@@ -0,0 +1,313 @@
+//! A minimal implementation of the Erlang external term format,
+//! see <https://www.erlang.org/doc/apps/erts/erl_ext_dist.html>.
+//!
+//! Terms are converted from and to JSON values, which is all that is
+//! needed to exchange stats and requests with the BEAM.
+
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+use std::fmt;
+
+pub const VERSION: u8 = 131;
+const NEW_FLOAT_EXT: u8 = 70;
+const SMALL_INTEGER_EXT: u8 = 97;
+const INTEGER_EXT: u8 = 98;
+const ATOM_EXT: u8 = 100;
+const SMALL_TUPLE_EXT: u8 = 104;
+const LARGE_TUPLE_EXT: u8 = 105;
+const NIL_EXT: u8 = 106;
+const STRING_EXT: u8 = 107;
+const LIST_EXT: u8 = 108;
+const BINARY_EXT: u8 = 109;
+const SMALL_BIG_EXT: u8 = 110;
+const SMALL_ATOM_EXT: u8 = 115;
+const MAP_EXT: u8 = 116;
+const ATOM_UTF8_EXT: u8 = 118;
+const SMALL_ATOM_UTF8_EXT: u8 = 119;
+
+/// Encodes a value using the Erlang external term format, so that it can
+/// be decoded with `binary_to_term/1`.
+///
+/// Objects become maps with atom keys, strings become binaries, arrays
+/// become lists and `null` becomes the `undefined` atom.
+pub fn encode<T: Serialize + fmt::Debug>(data: &T) -> Vec<u8> {
+    let value = serde_json::to_value(data)
+        .unwrap_or_else(|err| panic!("could not serialize {:?}: {}", data, err));
+
+    let mut out = vec![VERSION];
+    encode_value(&value, &mut out);
+    out
+}
+
+fn encode_value(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Null => encode_atom("undefined", out),
+        Value::Bool(b) => encode_atom(if *b { "true" } else { "false" }, out),
+        Value::Number(n) => {
+            if let Some(n) = n.as_i64() {
+                encode_integer(n.into(), out);
+            } else if let Some(n) = n.as_u64() {
+                encode_integer(n.into(), out);
+            } else {
+                out.push(NEW_FLOAT_EXT);
+                out.extend_from_slice(&n.as_f64().unwrap_or_default().to_be_bytes());
+            }
+        }
+        Value::String(s) => {
+            out.push(BINARY_EXT);
+            out.extend_from_slice(&(s.len() as u32).to_be_bytes());
+            out.extend_from_slice(s.as_bytes());
+        }
+        Value::Array(items) => {
+            if !items.is_empty() {
+                out.push(LIST_EXT);
+                out.extend_from_slice(&(items.len() as u32).to_be_bytes());
+                for item in items {
+                    encode_value(item, out);
+                }
+            }
+            out.push(NIL_EXT);
+        }
+        Value::Object(fields) => {
+            out.push(MAP_EXT);
+            out.extend_from_slice(&(fields.len() as u32).to_be_bytes());
+            for (key, value) in fields {
+                encode_atom(key, out);
+                encode_value(value, out);
+            }
+        }
+    }
+}
+
+fn encode_atom(name: &str, out: &mut Vec<u8>) {
+    if name.len() <= u8::MAX as usize {
+        out.push(SMALL_ATOM_UTF8_EXT);
+        out.push(name.len() as u8);
+    } else {
+        out.push(ATOM_UTF8_EXT);
+        out.extend_from_slice(&(name.len() as u16).to_be_bytes());
+    }
+    out.extend_from_slice(name.as_bytes());
+}
+
+fn encode_integer(n: i128, out: &mut Vec<u8>) {
+    if let Ok(n) = u8::try_from(n) {
+        out.push(SMALL_INTEGER_EXT);
+        out.push(n);
+    } else if let Ok(n) = i32::try_from(n) {
+        out.push(INTEGER_EXT);
+        out.extend_from_slice(&n.to_be_bytes());
+    } else {
+        // little-endian digits without the trailing zeroes
+        let digits = n.unsigned_abs().to_le_bytes();
+        let len = digits.iter().rposition(|d| *d != 0).map_or(0, |i| i + 1);
+        out.push(SMALL_BIG_EXT);
+        out.push(len as u8);
+        out.push(u8::from(n < 0));
+        out.extend_from_slice(&digits[..len]);
+    }
+}
+
+/// A decoded term, before it is converted to a JSON value.
+enum Term {
+    Integer(i128),
+    Float(f64),
+    Atom(String),
+    Binary(Vec<u8>),
+    List(Vec<Term>),
+    Tuple(Vec<Term>),
+    Map(Vec<(Term, Term)>),
+}
+
+/// Decodes a term produced by `term_to_binary/1`.
+///
+/// Maps and proplists with atom or binary keys become objects,
+/// the `true` and `false` atoms become booleans and `undefined` becomes
+/// `null`. A bare atom in a proplist is treated as `{Atom, true}`,
+/// just like `proplists:get_bool/2` does.
+pub fn decode(bytes: &[u8]) -> Result<Value, String> {
+    let mut decoder = Decoder { bytes, pos: 0 };
+    if decoder.u8()? != VERSION {
+        return Err("not an Erlang external term format binary".to_string());
+    }
+    let term = decoder.term()?;
+    if decoder.pos != bytes.len() {
+        return Err("unexpected trailing bytes after the term".to_string());
+    }
+    to_value(term)
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], String> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| "unexpected end of term".to_string())?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<usize, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]) as usize)
+    }
+
+    fn u32(&mut self) -> Result<usize, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    }
+
+    fn term(&mut self) -> Result<Term, String> {
+        let tag = self.u8()?;
+        match tag {
+            SMALL_INTEGER_EXT => Ok(Term::Integer(self.u8()?.into())),
+            INTEGER_EXT => {
+                let b = self.take(4)?;
+                Ok(Term::Integer(
+                    i32::from_be_bytes([b[0], b[1], b[2], b[3]]).into(),
+                ))
+            }
+            SMALL_BIG_EXT => {
+                let n = self.u8()? as usize;
+                let negative = self.u8()? != 0;
+                let digits = self.take(n)?;
+                if n > 16 {
+                    return Err("integer is too large".to_string());
+                }
+                let mut le = [0u8; 16];
+                le[..n].copy_from_slice(digits);
+                let magnitude = u128::from_le_bytes(le);
+                let value =
+                    i128::try_from(magnitude).map_err(|_| "integer is too large".to_string())?;
+                Ok(Term::Integer(if negative { -value } else { value }))
+            }
+            NEW_FLOAT_EXT => {
+                let b = self.take(8)?;
+                let mut be = [0u8; 8];
+                be.copy_from_slice(b);
+                Ok(Term::Float(f64::from_be_bytes(be)))
+            }
+            ATOM_EXT | ATOM_UTF8_EXT => {
+                let n = self.u16()?;
+                Ok(Term::Atom(
+                    String::from_utf8_lossy(self.take(n)?).into_owned(),
+                ))
+            }
+            SMALL_ATOM_EXT | SMALL_ATOM_UTF8_EXT => {
+                let n = self.u8()? as usize;
+                Ok(Term::Atom(
+                    String::from_utf8_lossy(self.take(n)?).into_owned(),
+                ))
+            }
+            BINARY_EXT => {
+                let n = self.u32()?;
+                Ok(Term::Binary(self.take(n)?.to_vec()))
+            }
+            STRING_EXT => {
+                let n = self.u16()?;
+                let chars = self.take(n)?;
+                Ok(Term::List(
+                    chars.iter().map(|c| Term::Integer((*c).into())).collect(),
+                ))
+            }
+            NIL_EXT => Ok(Term::List(Vec::new())),
+            LIST_EXT => {
+                let n = self.u32()?;
+                let items = (0..n).map(|_| self.term()).collect::<Result<_, _>>()?;
+                match self.term()? {
+                    Term::List(tail) if tail.is_empty() => Ok(Term::List(items)),
+                    _ => Err("improper lists are not supported".to_string()),
+                }
+            }
+            SMALL_TUPLE_EXT | LARGE_TUPLE_EXT => {
+                let n = if tag == SMALL_TUPLE_EXT {
+                    self.u8()? as usize
+                } else {
+                    self.u32()?
+                };
+                let items = (0..n).map(|_| self.term()).collect::<Result<_, _>>()?;
+                Ok(Term::Tuple(items))
+            }
+            MAP_EXT => {
+                let n = self.u32()?;
+                let pairs = (0..n)
+                    .map(|_| Ok((self.term()?, self.term()?)))
+                    .collect::<Result<_, String>>()?;
+                Ok(Term::Map(pairs))
+            }
+            other => Err(format!("unsupported term type {other}")),
+        }
+    }
+}
+
+fn to_value(term: Term) -> Result<Value, String> {
+    match term {
+        Term::Integer(n) => i64::try_from(n)
+            .map(Value::from)
+            .or_else(|_| u64::try_from(n).map(Value::from))
+            .map_err(|_| "integer is too large".to_string()),
+        Term::Float(f) => Number::from_f64(f)
+            .map(Value::Number)
+            .ok_or_else(|| "floats must be finite".to_string()),
+        Term::Atom(atom) => Ok(match atom.as_str() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "undefined" | "nil" => Value::Null,
+            _ => Value::String(atom),
+        }),
+        Term::Binary(bytes) => Ok(Value::String(String::from_utf8_lossy(&bytes).into_owned())),
+        Term::List(items) if !items.is_empty() && items.iter().all(is_property) => {
+            let mut object = Map::new();
+            for item in items {
+                let (key, value) = match item {
+                    Term::Tuple(mut pair) => {
+                        let value = pair.pop().map(to_value).transpose()?;
+                        (pair.pop(), value.unwrap_or(Value::Null))
+                    }
+                    atom => (Some(atom), Value::Bool(true)),
+                };
+                if let Some(Term::Atom(key)) = key {
+                    // the first occurrence of a key wins, as with proplists:get_value/2
+                    object.entry(key).or_insert(value);
+                }
+            }
+            Ok(Value::Object(object))
+        }
+        Term::List(items) | Term::Tuple(items) => items
+            .into_iter()
+            .map(to_value)
+            .collect::<Result<_, _>>()
+            .map(Value::Array),
+        Term::Map(pairs) => {
+            let mut object = Map::new();
+            for (key, value) in pairs {
+                let key = match key {
+                    Term::Atom(key) => key,
+                    Term::Binary(key) => String::from_utf8_lossy(&key).into_owned(),
+                    _ => return Err("map keys must be atoms or binaries".to_string()),
+                };
+                object.insert(key, to_value(value)?);
+            }
+            Ok(Value::Object(object))
+        }
+    }
+}
+
+fn is_property(term: &Term) -> bool {
+    match term {
+        Term::Atom(_) => true,
+        Term::Tuple(pair) => matches!(pair.as_slice(), [Term::Atom(_), _]),
+        _ => false,
+    }
+}
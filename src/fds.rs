@@ -1,7 +1,14 @@
+#[cfg(target_os = "linux")]
+mod fd_count;
+#[cfg(target_os = "linux")]
+mod sock_diag;
 #[cfg(target_os = "windows")]
 mod windows;
 
 use crate::outcome::*;
+use crate::source::Descriptor;
+#[cfg(target_os = "linux")]
+use crate::source::DescriptorTarget;
 #[cfg(target_os = "macos")]
 use libproc::libproc::{
     bsd_info::BSDInfo,
@@ -9,7 +16,12 @@ use libproc::libproc::{
     proc_pid::{listpidinfo, pidinfo},
 };
 #[cfg(target_os = "linux")]
-use procfs::process::{FDTarget, Process};
+use procfs::process::{FDTarget, LimitValue, Process};
+#[cfg(target_os = "linux")]
+use std::{collections::HashMap, fs, io, os::unix::fs::MetadataExt};
+
+#[cfg(target_os = "linux")]
+const MAX_USER_WATCHES_PATH: &str = "/proc/sys/fs/inotify/max_user_watches";
 
 pub struct FdList;
 
@@ -46,12 +58,8 @@ impl FdList {
         let info = pidinfo::<BSDInfo>(pid as i32, 0)?;
         let fds = listpidinfo::<ListFDs>(pid as i32, info.pbi_nfiles as usize)?;
 
-        let stats = ProcStats {
-            pid,
-            total_descriptors: fds.len() as u32,
-            socket_descriptors: None,
-            file_descriptors: None,
-        };
+        let mut stats = ProcStats::new(pid);
+        stats.total_descriptors = fds.len() as u32;
 
         Ok(stats)
     }
@@ -59,41 +67,111 @@ impl FdList {
 
 #[cfg(target_os = "linux")]
 impl FdList {
-    pub fn list_by_type(pid: Pid) -> Result<ProcStats, FshcError> {
+    pub fn descriptors(pid: Pid) -> Result<Vec<Descriptor>, FshcError> {
         let proc = Process::new(pid as i32)?;
-        let all_fds = proc.fd()?.flatten();
+        let descriptors = proc
+            .fd()?
+            .flatten()
+            .map(|fd| Descriptor {
+                fd: fd.fd,
+                target: match fd.target {
+                    FDTarget::Path(path) => DescriptorTarget::File(path.display().to_string()),
+                    FDTarget::Socket(inode) => DescriptorTarget::Socket(inode),
+                    FDTarget::Pipe(inode) => DescriptorTarget::Pipe(inode),
+                    FDTarget::AnonInode(kind) => DescriptorTarget::AnonInode(kind),
+                    _ => DescriptorTarget::Other,
+                },
+            })
+            .collect();
+
+        Ok(descriptors)
+    }
 
+    pub fn list_total(pid: Pid) -> Result<ProcStats, FshcError> {
         let mut stats = ProcStats::new(pid);
+        stats.total_descriptors = fd_count::count_fds(pid)?;
 
-        let mut fd_n = 0;
-        let mut sd_n = 0;
+        Ok(stats)
+    }
 
-        for fd in all_fds {
-            stats.total_descriptors += 1;
-            match fd.target {
-                FDTarget::Path(_) => fd_n += 1,
-                FDTarget::Socket(_) => sd_n += 1,
-                _ => (),
-            }
+    pub fn limit(pid: Pid) -> Result<Option<DescriptorLimit>, FshcError> {
+        let proc = Process::new(pid as i32)?;
+        let max_open_files = proc.limits()?.max_open_files;
+
+        let value = |limit: LimitValue| match limit {
+            LimitValue::Unlimited => None,
+            LimitValue::Value(n) => Some(n),
+        };
+        Ok(Some(DescriptorLimit {
+            soft: value(max_open_files.soft_limit),
+            hard: value(max_open_files.hard_limit),
+        }))
+    }
+
+    /// Returns `None` if the descriptor has been closed since it was listed.
+    pub fn fdinfo(pid: Pid, fd: i32) -> Result<Option<String>, FshcError> {
+        match fs::read_to_string(format!("/proc/{pid}/fdinfo/{fd}")) {
+            Ok(content) => Ok(Some(content)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
         }
+    }
 
-        stats.file_descriptors = Some(fd_n);
-        stats.socket_descriptors = Some(sd_n);
+    pub fn inode(pid: Pid, fd: i32) -> Option<u64> {
+        // follows the link to the file the descriptor refers to
+        let metadata = fs::metadata(format!("/proc/{pid}/fd/{fd}")).ok()?;
+        Some(metadata.ino())
+    }
 
-        Ok(stats)
+    /// The Unix domain socket table of the network namespace of a process.
+    pub fn unix_socket_table(pid: Pid) -> Result<Vec<String>, FshcError> {
+        let table = fs::read_to_string(format!("/proc/{pid}/net/unix"))?;
+        Ok(table.lines().skip(1).map(String::from).collect())
     }
 
-    pub fn list_total(pid: Pid) -> Result<ProcStats, FshcError> {
-        let proc = Process::new(pid as i32)?;
-        let total_descriptors = proc.fd()?.flatten().count() as u32;
+    pub fn maps(pid: Pid) -> Result<Vec<String>, FshcError> {
+        let maps = fs::read_to_string(format!("/proc/{pid}/maps"))?;
+        Ok(maps.lines().map(String::from).collect())
+    }
 
-        let stats = ProcStats {
-            pid,
-            total_descriptors,
-            socket_descriptors: None,
-            file_descriptors: None,
+    /// Only the sockets of processes in the network namespace of fshc can be
+    /// resolved, and only if the kernel has sock_diag support for Unix sockets.
+    pub fn unix_socket_peers(pid: Pid) -> Option<HashMap<u64, u64>> {
+        let namespace = |pid: &str| {
+            fs::metadata(format!("/proc/{pid}/ns/net"))
+                .ok()
+                .map(|metadata| metadata.ino())
         };
+        if namespace("self")? != namespace(&pid.to_string())? {
+            return None;
+        }
+        sock_diag::unix_socket_peers().ok()
+    }
 
-        Ok(stats)
+    pub fn max_user_inotify_watches() -> Option<u64> {
+        fs::read_to_string(MAX_USER_WATCHES_PATH)
+            .ok()
+            .and_then(|value| value.trim().parse().ok())
+    }
+
+    /// Listing `map_files` requires the same privileges as ptrace(2).
+    pub fn map_files_entries(pid: Pid) -> Option<u32> {
+        fs::read_dir(format!("/proc/{pid}/map_files"))
+            .ok()
+            .map(|entries| entries.count() as u32)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+impl FdList {
+    /// Other platforms only count descriptors by type.
+    pub fn descriptors(_pid: Pid) -> Result<Vec<Descriptor>, FshcError> {
+        Err(FshcError::Unsupported)
+    }
+
+    /// Only Linux exposes the descriptor limit of other processes,
+    /// Windows does not have a per-process handle limit.
+    pub fn limit(_pid: Pid) -> Result<Option<DescriptorLimit>, FshcError> {
+        Ok(None)
     }
 }
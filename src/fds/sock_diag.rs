@@ -0,0 +1,121 @@
+//! Resolves the peers of Unix domain sockets with the sock_diag netlink
+//! interface, see sock_diag(7). `/proc/net/unix` does not list them.
+
+use std::collections::HashMap;
+use std::io;
+
+use rustix::net::{AddressFamily, RecvFlags, SendFlags, SocketFlags, SocketType, netlink};
+
+// See linux/netlink.h, linux/sock_diag.h and linux/unix_diag.h
+const NLMSG_HEADER_LEN: usize = 16;
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+const NLM_F_REQUEST: u16 = 0x1;
+const NLM_F_DUMP: u16 = 0x300;
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+const AF_UNIX: u8 = 1;
+/// The length of `struct unix_diag_req`
+const UNIX_DIAG_REQ_LEN: usize = 24;
+/// The length of `struct unix_diag_msg`, which is followed by attributes
+const UNIX_DIAG_MSG_LEN: usize = 16;
+const UDIAG_SHOW_PEER: u32 = 0x4;
+const UNIX_DIAG_PEER: u16 = 2;
+
+/// Large enough for the messages the kernel puts in one datagram.
+const BUFFER_SIZE: usize = 32 * 1024;
+
+/// Maps the inode of every connected Unix domain socket in the network
+/// namespace of fshc to the inode of its peer.
+pub(super) fn unix_socket_peers() -> io::Result<HashMap<u64, u64>> {
+    let socket = rustix::net::socket_with(
+        AddressFamily::NETLINK,
+        SocketType::DGRAM,
+        SocketFlags::CLOEXEC,
+        Some(netlink::SOCK_DIAG),
+    )?;
+
+    let mut request = Vec::with_capacity(NLMSG_HEADER_LEN + UNIX_DIAG_REQ_LEN);
+    // struct nlmsghdr: length, type, flags, sequence number and port id
+    request.extend(((NLMSG_HEADER_LEN + UNIX_DIAG_REQ_LEN) as u32).to_ne_bytes());
+    request.extend(SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    request.extend((NLM_F_REQUEST | NLM_F_DUMP).to_ne_bytes());
+    request.extend(1u32.to_ne_bytes());
+    request.extend(0u32.to_ne_bytes());
+    // struct unix_diag_req: family, protocol, padding, states, inode, attributes and cookie
+    request.extend([AF_UNIX, 0, 0, 0]);
+    request.extend(u32::MAX.to_ne_bytes());
+    request.extend(0u32.to_ne_bytes());
+    request.extend(UDIAG_SHOW_PEER.to_ne_bytes());
+    request.extend([0; 8]);
+    rustix::net::sendto(
+        &socket,
+        &request,
+        SendFlags::empty(),
+        &netlink::SocketAddrNetlink::new(0, 0),
+    )?;
+
+    let mut peers = HashMap::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        let (len, _) = rustix::net::recv(&socket, &mut buffer[..], RecvFlags::empty())?;
+        let mut messages = &buffer[..len];
+        while messages.len() >= NLMSG_HEADER_LEN {
+            let message_len = u32_at(messages, 0) as usize;
+            if message_len < NLMSG_HEADER_LEN || message_len > messages.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "truncated sock_diag message",
+                ));
+            }
+            match u16_at(messages, 4) {
+                NLMSG_DONE => return Ok(peers),
+                // struct nlmsgerr starts with a negated errno
+                NLMSG_ERROR => {
+                    let errno = u32_at(messages, NLMSG_HEADER_LEN) as i32;
+                    return Err(io::Error::from_raw_os_error(-errno));
+                }
+                _ => add_peer(&messages[NLMSG_HEADER_LEN..message_len], &mut peers),
+            }
+            messages = &messages[align(message_len).min(messages.len())..];
+        }
+    }
+}
+
+/// Adds the peer of a `struct unix_diag_msg`, if it has one.
+fn add_peer(message: &[u8], peers: &mut HashMap<u64, u64>) {
+    if message.len() < UNIX_DIAG_MSG_LEN {
+        return;
+    }
+    let inode = u32_at(message, 4);
+
+    // struct rtattr: length and type, followed by the value
+    let mut attributes = &message[UNIX_DIAG_MSG_LEN..];
+    while attributes.len() >= 4 {
+        let len = u16_at(attributes, 0) as usize;
+        if len < 4 || len > attributes.len() {
+            return;
+        }
+        if u16_at(attributes, 2) == UNIX_DIAG_PEER && len >= 8 {
+            peers.insert(inode.into(), u32_at(attributes, 4).into());
+        }
+        attributes = &attributes[align(len).min(attributes.len())..];
+    }
+}
+
+/// Netlink messages and attributes are aligned to 4 bytes.
+fn align(len: usize) -> usize {
+    len.next_multiple_of(4)
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes([bytes[offset], bytes[offset + 1]])
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes([
+        bytes[offset],
+        bytes[offset + 1],
+        bytes[offset + 2],
+        bytes[offset + 3],
+    ])
+}
@@ -13,6 +13,10 @@ use windows_sys::Wdk::System::SystemInformation::{
 };
 use windows_sys::Win32::{
     Foundation::{
+        // https://learn.microsoft.com/en-us/windows/win32/api/handleapi/nf-handleapi-closehandle
+        CloseHandle as close_handle,
+        ERROR_ACCESS_DENIED,
+        ERROR_INVALID_PARAMETER,
         FALSE,
         // https://learn.microsoft.com/en-us/windows/win32/api/errhandlingapi/nf-errhandlingapi-getlasterror
         GetLastError as get_last_error,
@@ -168,10 +172,21 @@ impl FdList {
         let mut stats = ProcStats::new(pid);
 
         let process_handle = unsafe { open_process(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid) };
-        if unsafe { get_process_handle_count(process_handle, &mut stats.total_descriptors) }
-            == FALSE
-        {
+        if process_handle.is_null() {
             let code = unsafe { get_last_error() };
+            let errno = Some(code as i32);
+            return Err(match code {
+                // OpenProcess does not distinguish a missing process from other invalid pids
+                ERROR_INVALID_PARAMETER => FshcError::NotFound { errno },
+                ERROR_ACCESS_DENIED => FshcError::PermissionDenied { errno },
+                _ => FshcError::from(format!("Failed to open process with code {code}")),
+            });
+        }
+        let counted =
+            unsafe { get_process_handle_count(process_handle, &mut stats.total_descriptors) };
+        let code = unsafe { get_last_error() };
+        unsafe { close_handle(process_handle) };
+        if counted == FALSE {
             Err(FshcError::from(format!(
                 "Failed to get process handle count with code {code}"
             )))
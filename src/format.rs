@@ -0,0 +1,223 @@
+mod csv;
+mod influx;
+mod prometheus;
+mod statsd;
+mod table;
+
+use crate::etf;
+use crate::record::Sample;
+use crate::snapshot::SnapshotDiff;
+use crate::summary::{HistorySummary, Summary};
+use clap::ValueEnum;
+use fshc::outcome::*;
+use serde::Serialize;
+use std::fmt;
+use std::io::{self, IsTerminal};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// A single JSON object
+    #[default]
+    Json,
+    /// Prometheus text exposition format, e.g. for node_exporter's textfile collector
+    Prometheus,
+    /// Aligned columns for humans
+    Table,
+    /// Comma-separated values with a header row
+    Csv,
+    /// Tab-separated values with a header row
+    Tsv,
+    /// InfluxDB line protocol, e.g. for Telegraf's exec input
+    Influx,
+    /// StatsD gauges
+    Statsd,
+    /// Erlang external term format, decodable with binary_to_term/1
+    Etf,
+}
+
+impl OutputFormat {
+    /// Humans get a table, scripts and pipes get JSON.
+    pub fn detect() -> Self {
+        if io::stdout().is_terminal() {
+            OutputFormat::Table
+        } else {
+            OutputFormat::Json
+        }
+    }
+
+    /// Formats that describe their columns once, before any samples are rendered.
+    pub fn render_header(&self) -> Option<Vec<u8>> {
+        match self {
+            OutputFormat::Csv => Some(text(csv::render_header(','))),
+            OutputFormat::Tsv => Some(text(csv::render_header('\t'))),
+            _ => None,
+        }
+    }
+
+    pub fn render_stats(&self, stats: &ProcStats) -> Vec<u8> {
+        match self {
+            OutputFormat::Json => text(json(stats)),
+            OutputFormat::Prometheus => text(prometheus::render(&[stats])),
+            OutputFormat::Table => text(table::render(&[stats])),
+            OutputFormat::Csv => text(csv::render(&[stats], ',')),
+            OutputFormat::Tsv => text(csv::render(&[stats], '\t')),
+            OutputFormat::Influx => text(influx::render(&[stats])),
+            OutputFormat::Statsd => text(statsd::render(&[stats])),
+            OutputFormat::Etf => etf::encode(stats),
+        }
+    }
+
+    /// Failures are reported on standard error and are meant for humans and
+    /// process supervisors rather than metric collectors, so all formats
+    /// that do not have a natural error representation use JSON.
+    pub fn render_failure(&self, failure: &Failure) -> Vec<u8> {
+        match self {
+            OutputFormat::Table => text(table::render_failure(failure)),
+            OutputFormat::Etf => etf::encode(failure),
+            _ => text(json(failure)),
+        }
+    }
+
+    /// Whether the rendered stats include the descriptor limit, which is
+    /// otherwise only collected when it is asked for.
+    pub fn renders_limit(&self) -> bool {
+        !matches!(self, OutputFormat::Json | OutputFormat::Etf)
+    }
+
+    /// Whether [`OutputFormat::render_report`] includes the failures of a report.
+    /// If it does not, they have to be reported separately.
+    pub fn embeds_failures(&self) -> bool {
+        matches!(
+            self,
+            OutputFormat::Json | OutputFormat::Table | OutputFormat::Etf
+        )
+    }
+
+    /// Snapshot diffs are only rendered as JSON and tables.
+    pub fn render_diff(&self, diff: &SnapshotDiff) -> Vec<u8> {
+        match self {
+            OutputFormat::Table => text(table::render_diff(diff)),
+            _ => text(json(diff)),
+        }
+    }
+
+    /// Recorded samples are rendered as a table, or as JSON with one document per sample.
+    pub fn render_samples(&self, samples: &[Sample]) -> Vec<u8> {
+        match self {
+            OutputFormat::Table => text(table::render_samples(samples)),
+            _ => samples
+                .iter()
+                .flat_map(|sample| text(json(sample)))
+                .collect(),
+        }
+    }
+
+    /// Summaries of sampled descriptors are only rendered as JSON and tables.
+    pub fn render_summary(&self, summary: &HistorySummary) -> Vec<u8> {
+        match self {
+            OutputFormat::Table => text(table::render_summary(summary)),
+            _ => text(json(summary)),
+        }
+    }
+
+    pub fn render_report(&self, report: &Report) -> Vec<u8> {
+        let all_stats: Vec<&ProcStats> = report.processes.iter().collect();
+        if all_stats.is_empty() && !self.embeds_failures() {
+            // The failures are reported separately, there is nothing to render
+            return Vec::new();
+        }
+        match self {
+            OutputFormat::Json => text(json(report)),
+            OutputFormat::Prometheus => text(prometheus::render(&all_stats)),
+            OutputFormat::Csv => text(csv::render(&all_stats, ',')),
+            OutputFormat::Tsv => text(csv::render(&all_stats, '\t')),
+            OutputFormat::Influx => text(influx::render(&all_stats)),
+            OutputFormat::Statsd => text(statsd::render(&all_stats)),
+            OutputFormat::Etf => etf::encode(report),
+            OutputFormat::Table => {
+                let mut lines = Vec::new();
+                if !all_stats.is_empty() {
+                    lines.push(table::render(&all_stats));
+                }
+                lines.extend(report.failures.iter().map(table::render_failure));
+                text(lines.join("\n"))
+            }
+        }
+    }
+}
+
+/// The numeric fields of [`ProcStats`] in a stable order, shared by the flat,
+/// line-oriented formats. Fields that were not collected are `None`.
+fn fields(stats: &ProcStats) -> [(&'static str, Option<u64>); 19] {
+    let limit = stats.descriptor_limit;
+    let flags = stats.fd_flags.as_ref();
+    let watches = stats.watches.as_ref();
+    let mappings = stats.mappings.as_ref();
+
+    [
+        ("total_descriptors", Some(stats.total_descriptors.into())),
+        (
+            "socket_descriptors",
+            stats.socket_descriptors.map(u64::from),
+        ),
+        ("file_descriptors", stats.file_descriptors.map(u64::from)),
+        ("descriptor_limit_soft", limit.and_then(|l| l.soft)),
+        ("descriptor_limit_hard", limit.and_then(|l| l.hard)),
+        (
+            "unix_sockets",
+            stats
+                .unix_sockets
+                .as_ref()
+                .map(|groups| groups.iter().map(|g| u64::from(g.count)).sum()),
+        ),
+        ("read_only", flags.map(|f| f.read_only.into())),
+        ("write_only", flags.map(|f| f.write_only.into())),
+        ("read_write", flags.map(|f| f.read_write.into())),
+        ("non_blocking", flags.map(|f| f.non_blocking.into())),
+        ("without_cloexec", flags.map(|f| f.without_cloexec.into())),
+        ("epoll_instances", watches.map(|w| w.epoll_instances.into())),
+        ("epoll_watches", watches.map(|w| w.epoll_watches.into())),
+        (
+            "inotify_instances",
+            watches.map(|w| w.inotify_instances.into()),
+        ),
+        ("inotify_watches", watches.map(|w| w.inotify_watches.into())),
+        (
+            "max_user_inotify_watches",
+            watches.and_then(|w| w.max_user_inotify_watches),
+        ),
+        (
+            "locks",
+            stats.locks.as_ref().map(|locks| locks.len() as u64),
+        ),
+        ("mapped_files", mappings.map(|m| m.mapped_files.into())),
+        (
+            "mapped_without_descriptor",
+            mappings.map(|m| m.without_descriptor.into()),
+        ),
+    ]
+}
+
+/// Text formats are printed one document per line.
+fn text(rendered: String) -> Vec<u8> {
+    let mut bytes = rendered.into_bytes();
+    bytes.push(b'\n');
+    bytes
+}
+
+/// A top-level JSON document, tagged with the version of its schema.
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+fn json<T: Serialize + fmt::Debug>(data: &T) -> String {
+    let document = Versioned {
+        schema_version: SCHEMA_VERSION,
+        data,
+    };
+    serde_json::to_string(&document)
+        .unwrap_or_else(|err| panic!("could not serialize {:?}: {}", data, err))
+}
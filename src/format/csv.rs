@@ -0,0 +1,33 @@
+use super::*;
+
+use crate::time::{format_timestamp, unix_timestamp};
+use std::time::SystemTime;
+
+/// The header row. Columns are only ever appended to keep
+/// previously captured samples compatible.
+pub fn render_header(separator: char) -> String {
+    let columns = ["timestamp", "pid"]
+        .into_iter()
+        .chain(fields(&ProcStats::new(0)).map(|(name, _)| name));
+
+    columns.collect::<Vec<_>>().join(&separator.to_string())
+}
+
+/// Renders one row per process. Values that were not collected are left empty.
+pub fn render(all_stats: &[&ProcStats], separator: char) -> String {
+    let timestamp = format_timestamp(unix_timestamp(SystemTime::now()));
+
+    all_stats
+        .iter()
+        .map(|stats| {
+            let values = [timestamp.clone(), stats.pid.to_string()]
+                .into_iter()
+                .chain(
+                    fields(stats)
+                        .map(|(_, value)| value.map(|v| v.to_string()).unwrap_or_default()),
+                );
+            values.collect::<Vec<_>>().join(&separator.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,33 @@
+use super::*;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const MEASUREMENT: &str = "fshc";
+
+/// Renders one point per process, tagged with the pid, using the
+/// InfluxDB line protocol with a nanosecond precision timestamp, see
+/// <https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/>
+pub fn render(all_stats: &[&ProcStats]) -> String {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+
+    all_stats
+        .iter()
+        .map(|stats| {
+            let fields: Vec<String> = fields(stats)
+                .into_iter()
+                .filter_map(|(name, value)| value.map(|v| format!("{name}={v}i")))
+                .collect();
+            format!(
+                "{},pid={} {} {}",
+                MEASUREMENT,
+                stats.pid,
+                fields.join(","),
+                timestamp
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
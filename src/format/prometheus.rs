@@ -0,0 +1,173 @@
+use super::*;
+
+use std::fmt::Write;
+
+/// A metric family: one `HELP` and `TYPE` header followed by its samples.
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    samples: Vec<(String, u64)>,
+}
+
+impl Family {
+    fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            samples: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, pid: Pid, labels: &[(&str, &str)], value: u64) {
+        let mut rendered = format!("pid=\"{pid}\"");
+        for (name, value) in labels {
+            let _ = write!(rendered, ",{}=\"{}\"", name, escape(value));
+        }
+        self.samples.push((rendered, value));
+    }
+
+    fn write_to(&self, out: &mut String) {
+        if self.samples.is_empty() {
+            return;
+        }
+        let _ = writeln!(out, "# HELP {} {}", self.name, self.help);
+        let _ = writeln!(out, "# TYPE {} gauge", self.name);
+        for (labels, value) in &self.samples {
+            let _ = writeln!(out, "{}{{{}}} {}", self.name, labels, value);
+        }
+    }
+}
+
+/// Renders the stats of one or more processes as gauges, grouping
+/// the samples of every process under a single metric family header.
+pub fn render(all_stats: &[&ProcStats]) -> String {
+    // Totals get families of their own, so that summing a family never counts a descriptor twice
+    let mut open_descriptors = Family::new(
+        "fshc_open_descriptors",
+        "All open descriptors (handles) of a process",
+    );
+    let mut descriptors = Family::new(
+        "fshc_descriptors",
+        "Open socket and file descriptors of a process by type, other types are only part of fshc_open_descriptors",
+    );
+    let mut limits = Family::new(
+        "fshc_descriptor_limit",
+        "The maximum number of open descriptors (RLIMIT_NOFILE) of a process",
+    );
+    let mut flags = Family::new(
+        "fshc_descriptor_flags",
+        "Open descriptors of a process by access mode and status flag",
+    );
+    let mut unix_sockets = Family::new(
+        "fshc_unix_sockets",
+        "Unix domain sockets of a process by bound path, peer path and socket type",
+    );
+    let mut watches = Family::new(
+        "fshc_watches",
+        "Descriptors registered with epoll and watches registered with inotify",
+    );
+    let mut max_user_watches = Family::new(
+        "fshc_inotify_max_user_watches",
+        "The fs.inotify.max_user_watches limit",
+    );
+    let mut locks = Family::new(
+        "fshc_file_locks",
+        "File locks held by a process by lock type",
+    );
+    let mut mapped_files = Family::new(
+        "fshc_mapped_files",
+        "Distinct files mapped into the address space of a process",
+    );
+    let mut mapped_without_descriptor = Family::new(
+        "fshc_mapped_files_without_descriptor",
+        "Mapped files of a process that it no longer has a descriptor for",
+    );
+
+    for stats in all_stats {
+        let pid = stats.pid;
+        open_descriptors.add(pid, &[], stats.total_descriptors.into());
+        if let Some(n) = stats.socket_descriptors {
+            descriptors.add(pid, &[("type", "socket")], n.into());
+        }
+        if let Some(n) = stats.file_descriptors {
+            descriptors.add(pid, &[("type", "file")], n.into());
+        }
+        if let Some(limit) = stats.descriptor_limit {
+            if let Some(n) = limit.soft {
+                limits.add(pid, &[("kind", "soft")], n);
+            }
+            if let Some(n) = limit.hard {
+                limits.add(pid, &[("kind", "hard")], n);
+            }
+        }
+        if let Some(f) = &stats.fd_flags {
+            flags.add(pid, &[("flag", "read_only")], f.read_only.into());
+            flags.add(pid, &[("flag", "write_only")], f.write_only.into());
+            flags.add(pid, &[("flag", "read_write")], f.read_write.into());
+            flags.add(pid, &[("flag", "non_blocking")], f.non_blocking.into());
+            flags.add(
+                pid,
+                &[("flag", "without_cloexec")],
+                f.without_cloexec.into(),
+            );
+        }
+        for group in stats.unix_sockets.iter().flatten() {
+            unix_sockets.add(
+                pid,
+                &[
+                    ("path", group.path.as_deref().unwrap_or("")),
+                    ("peer", group.peer.as_deref().unwrap_or("")),
+                    ("socket_type", group.socket_type.as_str()),
+                ],
+                group.count.into(),
+            );
+        }
+        if let Some(w) = &stats.watches {
+            watches.add(pid, &[("kind", "epoll")], w.epoll_watches.into());
+            watches.add(pid, &[("kind", "inotify")], w.inotify_watches.into());
+            if let Some(n) = w.max_user_inotify_watches {
+                max_user_watches.add(pid, &[], n);
+            }
+        }
+        if let Some(all_locks) = &stats.locks {
+            for lock_type in FileLockType::ALL {
+                let n = all_locks
+                    .iter()
+                    .filter(|l| l.lock_type == lock_type)
+                    .count();
+                locks.add(pid, &[("lock_type", lock_type.as_str())], n as u64);
+            }
+        }
+        if let Some(m) = &stats.mappings {
+            mapped_files.add(pid, &[], m.mapped_files.into());
+            mapped_without_descriptor.add(pid, &[], m.without_descriptor.into());
+        }
+    }
+
+    let mut out = String::new();
+    for family in [
+        open_descriptors,
+        descriptors,
+        limits,
+        flags,
+        unix_sockets,
+        watches,
+        max_user_watches,
+        locks,
+        mapped_files,
+        mapped_without_descriptor,
+    ] {
+        family.write_to(&mut out);
+    }
+    out.truncate(out.trim_end().len());
+    out
+}
+
+/// Escapes a label value, see
+/// <https://prometheus.io/docs/instrumenting/exposition_formats/#text-format-details>
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,17 @@
+use super::*;
+
+const PREFIX: &str = "fshc";
+
+/// Renders every collected value as a StatsD gauge named
+/// `fshc.<pid>.<field>`, since plain StatsD does not support tags.
+pub fn render(all_stats: &[&ProcStats]) -> String {
+    all_stats
+        .iter()
+        .flat_map(|stats| {
+            fields(stats).into_iter().filter_map(|(name, value)| {
+                value.map(|v| format!("{}.{}.{}:{}|g", PREFIX, stats.pid, name, v))
+            })
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
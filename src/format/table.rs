@@ -0,0 +1,339 @@
+use super::*;
+
+use std::fmt::Write;
+
+#[derive(Clone, Copy)]
+enum Align {
+    Left,
+    Right,
+}
+
+/// A table with aligned columns, rendered the way `ps` or `ss` would.
+struct Table {
+    columns: Vec<(&'static str, Align)>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    fn new(columns: &[(&'static str, Align)]) -> Self {
+        Self {
+            columns: columns.to_vec(),
+            rows: Vec::new(),
+        }
+    }
+
+    fn add(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    fn write_to(&self, out: &mut String) {
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| {
+                self.rows
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([name.len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let header: Vec<String> = self.columns.iter().map(|(n, _)| n.to_string()).collect();
+        for row in [&header].into_iter().chain(&self.rows) {
+            let mut line = String::new();
+            for (i, cell) in row.iter().enumerate() {
+                if i > 0 {
+                    line.push_str("  ");
+                }
+                let _ = match self.columns[i].1 {
+                    Align::Left => write!(line, "{:<width$}", cell, width = widths[i]),
+                    Align::Right => write!(line, "{:>width$}", cell, width = widths[i]),
+                };
+            }
+            let _ = writeln!(out, "{}", line.trim_end());
+        }
+    }
+}
+
+/// Renders the descriptor counts of one or more processes as one row per
+/// process, followed by a section for every detailed report that was requested.
+pub fn render(all_stats: &[&ProcStats]) -> String {
+    let mut out = String::new();
+
+    let mut summary = Table::new(&[
+        ("PID", Align::Right),
+        ("TOTAL", Align::Right),
+        ("SOCKETS", Align::Right),
+        ("FILES", Align::Right),
+        ("LIMIT", Align::Right),
+        ("UTILIZATION", Align::Right),
+    ]);
+    for stats in all_stats {
+        let soft_limit = stats.descriptor_limit.and_then(|l| l.soft);
+        summary.add(vec![
+            stats.pid.to_string(),
+            stats.total_descriptors.to_string(),
+            optional(stats.socket_descriptors),
+            optional(stats.file_descriptors),
+            optional(soft_limit),
+            soft_limit
+                .filter(|limit| *limit > 0)
+                .map(|limit| {
+                    format!(
+                        "{:.1}%",
+                        f64::from(stats.total_descriptors) * 100.0 / limit as f64
+                    )
+                })
+                .unwrap_or_else(|| "-".to_string()),
+        ]);
+    }
+    summary.write_to(&mut out);
+
+    for stats in all_stats {
+        write_details(stats, &mut out);
+    }
+
+    out.truncate(out.trim_end().len());
+    out
+}
+
+/// Renders the descriptors opened and closed between two snapshots.
+pub fn render_diff(diff: &SnapshotDiff) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Process {} had {} descriptors at {}, process {} had {} at {}\n",
+        diff.before.pid,
+        diff.before.total_descriptors,
+        diff.before.taken_at,
+        diff.after.pid,
+        diff.after.total_descriptors,
+        diff.after.taken_at
+    );
+
+    if diff.opened.is_empty() && diff.closed.is_empty() {
+        out.push_str("No descriptors were opened or closed");
+        return out;
+    }
+
+    let mut table = Table::new(&[
+        ("CHANGE", Align::Left),
+        ("TYPE", Align::Left),
+        ("PATH", Align::Left),
+        ("COUNT", Align::Right),
+    ]);
+    let changes = [("opened", &diff.opened), ("closed", &diff.closed)];
+    for (change, groups) in changes {
+        for group in groups {
+            table.add(vec![
+                change.to_string(),
+                group.kind.to_string(),
+                optional(group.path.as_deref()),
+                group.count.to_string(),
+            ]);
+        }
+    }
+    table.write_to(&mut out);
+
+    out.truncate(out.trim_end().len());
+    out
+}
+
+/// Renders recorded samples, oldest first.
+pub fn render_samples(samples: &[Sample]) -> String {
+    let mut out = String::new();
+    let mut table = Table::new(&[
+        ("SAMPLED AT", Align::Left),
+        ("PID", Align::Right),
+        ("TOTAL", Align::Right),
+        ("SOCKETS", Align::Right),
+        ("FILES", Align::Right),
+    ]);
+    for sample in samples {
+        table.add(vec![
+            sample.sampled_at.clone(),
+            sample.pid.to_string(),
+            sample.total_descriptors.to_string(),
+            optional(sample.socket_descriptors),
+            optional(sample.file_descriptors),
+        ]);
+    }
+    table.write_to(&mut out);
+
+    out.truncate(out.trim_end().len());
+    out
+}
+
+pub fn render_summary(summary: &HistorySummary) -> String {
+    let process = summary
+        .pid
+        .map(|pid| format!("Process {}: ", pid))
+        .unwrap_or_default();
+    let (Some(first), Some(last)) = (&summary.first_sampled_at, &summary.last_sampled_at) else {
+        return format!("{}No samples were taken", process);
+    };
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}{} samples from {} to {}\n",
+        process, summary.samples, first, last
+    );
+    write_summaries(&summary.categories, &mut out);
+
+    out.truncate(out.trim_end().len());
+    out
+}
+
+fn write_summaries(summaries: &[Summary], out: &mut String) {
+    let mut table = Table::new(&[
+        ("CATEGORY", Align::Left),
+        ("MIN", Align::Right),
+        ("MAX", Align::Right),
+        ("MEAN", Align::Right),
+        ("P50", Align::Right),
+        ("P95", Align::Right),
+        ("P99", Align::Right),
+        ("PEAK AT", Align::Left),
+    ]);
+    for summary in summaries {
+        table.add(vec![
+            summary.category.to_string(),
+            summary.min.to_string(),
+            summary.max.to_string(),
+            format!("{:.1}", summary.mean),
+            summary.p50.to_string(),
+            summary.p95.to_string(),
+            summary.p99.to_string(),
+            summary.peak_at.clone(),
+        ]);
+    }
+    table.write_to(out);
+}
+
+pub fn render_failure(failure: &Failure) -> String {
+    format!("{}: {}", failure.message, failure.details)
+}
+
+fn write_details(stats: &ProcStats, out: &mut String) {
+    let pid = stats.pid;
+
+    if let Some(flags) = &stats.fd_flags {
+        let _ = writeln!(out, "\nDescriptor flags of process {pid}");
+        let mut table = Table::new(&[
+            ("READ_ONLY", Align::Right),
+            ("WRITE_ONLY", Align::Right),
+            ("READ_WRITE", Align::Right),
+            ("NON_BLOCKING", Align::Right),
+            ("WITHOUT_CLOEXEC", Align::Right),
+        ]);
+        table.add(vec![
+            flags.read_only.to_string(),
+            flags.write_only.to_string(),
+            flags.read_write.to_string(),
+            flags.non_blocking.to_string(),
+            flags.without_cloexec.to_string(),
+        ]);
+        table.write_to(out);
+    }
+
+    if let Some(groups) = &stats.unix_sockets {
+        let _ = writeln!(out, "\nUnix domain sockets of process {pid}");
+        let mut table = Table::new(&[
+            ("PATH", Align::Left),
+            ("PEER", Align::Left),
+            ("TYPE", Align::Left),
+            ("COUNT", Align::Right),
+        ]);
+        for group in groups {
+            table.add(vec![
+                group
+                    .path
+                    .clone()
+                    .unwrap_or_else(|| "(unbound)".to_string()),
+                group.peer.clone().unwrap_or_else(|| "-".to_string()),
+                group.socket_type.as_str().to_string(),
+                group.count.to_string(),
+            ]);
+        }
+        table.write_to(out);
+    }
+
+    if let Some(watches) = &stats.watches {
+        let _ = writeln!(out, "\nepoll and inotify watches of process {pid}");
+        let mut table = Table::new(&[
+            ("KIND", Align::Left),
+            ("INSTANCES", Align::Right),
+            ("WATCHES", Align::Right),
+            ("MAX_USER_WATCHES", Align::Right),
+        ]);
+        table.add(vec![
+            "epoll".to_string(),
+            watches.epoll_instances.to_string(),
+            watches.epoll_watches.to_string(),
+            "-".to_string(),
+        ]);
+        table.add(vec![
+            "inotify".to_string(),
+            watches.inotify_instances.to_string(),
+            watches.inotify_watches.to_string(),
+            optional(watches.max_user_inotify_watches),
+        ]);
+        table.write_to(out);
+    }
+
+    if let Some(locks) = &stats.locks {
+        let _ = writeln!(out, "\nFile locks of process {pid}");
+        let mut table = Table::new(&[
+            ("PATH", Align::Left),
+            ("TYPE", Align::Left),
+            ("KIND", Align::Left),
+            ("MODE", Align::Left),
+        ]);
+        for lock in locks {
+            table.add(vec![
+                lock.path.clone(),
+                lock.lock_type.as_str().to_string(),
+                lock.kind.as_str().to_string(),
+                lock.mode.as_str().to_string(),
+            ]);
+        }
+        table.write_to(out);
+    }
+
+    if let Some(mappings) = &stats.mappings {
+        let _ = writeln!(
+            out,
+            "\nMapped files of process {pid} ({} distinct, {} without a descriptor)",
+            mappings.mapped_files, mappings.without_descriptor
+        );
+        let mut table = Table::new(&[
+            ("PATH", Align::Left),
+            ("MAPPINGS", Align::Right),
+            ("DESCRIPTOR", Align::Left),
+            ("DELETED", Align::Left),
+        ]);
+        for file in &mappings.files {
+            table.add(vec![
+                file.path.clone(),
+                file.mappings.to_string(),
+                yes_no(file.has_descriptor),
+                yes_no(file.deleted),
+            ]);
+        }
+        table.write_to(out);
+    }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
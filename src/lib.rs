@@ -0,0 +1,107 @@
+//! File and socket handle counter.
+//!
+//! Counts the file and socket descriptors a process has open and, on Linux,
+//! optionally reports what they are used for: Unix domain sockets,
+//! descriptor flags, epoll and inotify watches, file locks and memory-mapped files.
+//!
+//! ```no_run
+//! use fshc::{QueryOptions, count_descriptors};
+//!
+//! let options = QueryOptions {
+//!     unix_sockets: true,
+//!     ..Default::default()
+//! };
+//! let stats = count_descriptors(std::process::id(), &options)?;
+//! println!("{} descriptors", stats.total_descriptors);
+//! # Ok::<(), fshc::FshcError>(())
+//! ```
+
+mod fds;
+pub mod outcome;
+mod reports;
+pub mod scan;
+pub mod source;
+#[cfg(feature = "async")]
+pub mod stream;
+
+use serde::Deserialize;
+
+pub use crate::outcome::{Failure, FailureCode, FshcError, FshcResult, Pid, ProcStats};
+use crate::source::{DescriptorSource, System};
+
+/// The highest pid [`count_descriptors`] accepts.
+pub const PID_LIMIT: Pid = 99_999;
+
+/// What to collect about the target process. Omitted options are off.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QueryOptions {
+    /// Only count descriptors, without telling sockets and files apart
+    pub only_total: bool,
+    /// Report the soft and hard descriptor limits (RLIMIT_NOFILE) of the process
+    pub limit: bool,
+    /// Group Unix domain sockets by bound path and socket type (Linux only)
+    pub unix_sockets: bool,
+    /// Summarize access modes, O_NONBLOCK and missing O_CLOEXEC (Linux only)
+    pub fd_flags: bool,
+    /// Count epoll and inotify instances and their registered watches (Linux only)
+    pub watches: bool,
+    /// List POSIX, flock and OFD locks held on open files (Linux only)
+    pub locks: bool,
+    /// List memory-mapped files, including the ones without a descriptor (Linux only)
+    pub mappings: bool,
+}
+
+/// Counts the descriptors of a process and collects the reports enabled in `options`.
+///
+/// Reports that are not supported on the current platform fail with
+/// an `Unsupported` error rather than being silently left out.
+pub fn count_descriptors(pid: Pid, options: &QueryOptions) -> FshcResult {
+    count_descriptors_with(&System, pid, options)
+}
+
+/// Like [`count_descriptors`], but reads descriptors from the given source,
+/// e.g. a [`source::FakeSource`] in tests.
+pub fn count_descriptors_with(
+    source: &impl DescriptorSource,
+    pid: Pid,
+    options: &QueryOptions,
+) -> FshcResult {
+    let pid = validate_pid(pid)?;
+    let mut stats = if options.only_total {
+        source.list_total(pid)?
+    } else {
+        source.list_by_type(pid)?
+    };
+    if options.limit {
+        // The limit is informational, failing to read it must not fail the count
+        stats.descriptor_limit = source.limit(pid).ok().flatten();
+    }
+
+    if options.unix_sockets {
+        stats.unix_sockets = Some(reports::unix_sockets(source, pid)?);
+    }
+    if options.fd_flags {
+        stats.fd_flags = Some(reports::fd_flags(source, pid)?);
+    }
+    if options.watches {
+        stats.watches = Some(reports::watches(source, pid)?);
+    }
+    if options.locks {
+        stats.locks = Some(reports::locks(source, pid)?);
+    }
+    if options.mappings {
+        stats.mappings = Some(reports::mappings(source, pid)?);
+    }
+
+    Ok(stats)
+}
+
+/// Fails with [`FshcError::PidOutOfRange`] for pids outside of 1 to [`PID_LIMIT`].
+pub fn validate_pid(pid: Pid) -> Result<Pid, FshcError> {
+    if (1..=PID_LIMIT).contains(&pid) {
+        Ok(pid)
+    } else {
+        Err(FshcError::PidOutOfRange)
+    }
+}
@@ -2,76 +2,243 @@
 #[global_allocator]
 static GLOBAL: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-mod fds;
-mod outcome;
+mod batch;
+mod cli;
+mod etf;
+mod format;
+mod output;
+mod port;
+mod record;
+mod request;
+mod schema;
+mod serve;
+mod snapshot;
+mod summary;
+mod time;
+mod wait;
 
-use clap::Parser;
-use serde::Serialize;
-use std::fmt;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+use std::io::{self, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, SystemTime};
 use sysexits::ExitCode;
 
-use crate::fds::*;
-use crate::outcome::*;
+use fshc::QueryOptions;
+use fshc::outcome::*;
+use fshc::scan::Scanner;
 
-const PID_LIMIT: u32 = 99_999;
-
-#[derive(Parser, Debug)]
-#[command(version = clap::crate_version!(), about = "File and socket handle counter", long_about = None, after_help = "GitHub: https://github.com/rabbitmq/fshc")]
-struct CliArgs {
-    #[arg(long)]
-    only_total: bool,
-    #[arg(short, long)]
-    pid: u32,
-}
+use crate::cli::*;
+use crate::format::OutputFormat;
+use crate::output::Output;
+use crate::summary::History;
+use crate::time::unix_timestamp;
 
 fn main() -> ExitCode {
     let args = CliArgs::parse();
-    let res = run(&args);
 
-    terminate(res, &args)
+    match &args.command {
+        Some(Command::Serve(serve_args)) => serve::run(serve_args),
+        Some(Command::Port(port_args)) => port::run(port_args),
+        Some(Command::Batch) => batch::run(),
+        Some(Command::Schema) => exit(schema::render(), ExitCode::Ok),
+        Some(Command::Snapshot(snapshot_args)) => snapshot::snapshot(snapshot_args),
+        Some(Command::Diff(diff_args)) => snapshot::diff(diff_args),
+        Some(Command::Record(record_args)) => record::record(record_args),
+        Some(Command::Replay(replay_args)) => record::replay(replay_args),
+        Some(Command::Wait(wait_args)) => wait::run(wait_args),
+        None => sample(&args),
+    }
 }
 
-fn run(args: &CliArgs) -> FshcResult {
-    let pid = validate_pid(args)?;
-    let stats = if args.only_total {
-        FdList::list_total(pid)?
-    } else {
-        FdList::list_by_type(pid)?
+/// Queries the target processes once, or repeatedly if an interval is given.
+/// Sampling stops early when none of the processes can be inspected anymore.
+fn sample(args: &CliArgs) -> ExitCode {
+    let (format, output) = match (args.format, args.statsd_addr) {
+        (None | Some(OutputFormat::Statsd), Some(addr)) => match Output::statsd(addr) {
+            Ok(output) => (OutputFormat::Statsd, output),
+            Err(err) => {
+                let failure = Failure::io(
+                    format!("Failed to set up a StatsD client for {}", addr),
+                    &err,
+                );
+                return exit(OutputFormat::Json.render_failure(&failure), err.exit_code());
+            }
+        },
+        (Some(_), Some(_)) => CliArgs::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--statsd-addr can only be used with --format statsd",
+            )
+            .exit(),
+        (format, None) => (format.unwrap_or_else(OutputFormat::detect), Output::Stdout),
+    };
+    let scanner = Scanner::new(args.jobs.into(), args.timeout);
+    if let (true, Some(interval)) = (args.summary, args.interval) {
+        return summarize(args, interval, &scanner, format);
+    }
+    let query = QueryOptions {
+        limit: args.query.limit || format.renders_limit(),
+        ..QueryOptions::from(&args.query)
     };
 
-    Ok(stats)
-}
+    // Written before the first sample that has any stats, so that nothing
+    // but the failures is printed when every process fails
+    let mut header = format.render_header();
+    let mut taken = 0;
+    loop {
+        let mut outcomes = scanner.scan(&args.pid, &query);
+        if outcomes.iter().any(Result::is_ok)
+            && let Some(header) = header.take()
+        {
+            output.write(&header);
+        }
+        let (code, any_succeeded) = match args.pid.as_slice() {
+            [pid] => {
+                let res = outcomes.remove(0);
+                let succeeded = res.is_ok();
+                (terminate(res, *pid, format, &output), succeeded)
+            }
+            pids => report(pids, outcomes, format, &output),
+        };
+        taken += 1;
 
-fn terminate(outcome: FshcResult, args: &CliArgs) -> ExitCode {
-    match outcome {
-        Ok(stats) => exit(stats, ExitCode::Ok),
-        Err(err) => {
-            let failure = Failure {
-                message: &format!(
-                    "Failed to obtain file and socket descriptors of process {}",
-                    args.pid
-                ),
-                details: &err.to_string(),
-            };
-            exit(failure, err.exit_code())
+        match args.interval {
+            Some(interval) if any_succeeded && args.count.is_none_or(|n| taken < n) => {
+                thread::sleep(interval)
+            }
+            _ => return code,
         }
     }
 }
 
-fn exit<T: Serialize + fmt::Debug>(data: T, code: ExitCode) -> ExitCode {
-    let output = serde_json::to_string(&data)
-        .unwrap_or_else(|err| panic!("could not serialize {:?}: {}", data, err));
-    match code {
-        ExitCode::Ok => println!("{}", output),
-        _ => eprintln!("{}", output),
+/// Samples the target processes like [`sample`] but only keeps their descriptor counts,
+/// which are summarized when sampling stops or is interrupted with Ctrl+C.
+fn summarize(
+    args: &CliArgs,
+    interval: Duration,
+    scanner: &Scanner,
+    format: OutputFormat,
+) -> ExitCode {
+    if !matches!(format, OutputFormat::Json | OutputFormat::Table) {
+        CliArgs::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "--summary only supports --format json and --format table",
+            )
+            .exit()
+    }
+
+    let (interrupt, interrupted) = mpsc::channel();
+    ctrlc::set_handler(move || {
+        let _ = interrupt.send(());
+    })
+    .unwrap_or_else(|err| panic!("could not install a Ctrl+C handler: {}", err));
+
+    let mut histories: Vec<History> = args.pid.iter().map(|_| History::default()).collect();
+    let mut taken = 0;
+    let code = loop {
+        let sampled_at = unix_timestamp(SystemTime::now());
+        let outcomes = scanner.scan(&args.pid, &QueryOptions::from(&args.query));
+        let mut code = ExitCode::Ok;
+        let mut any_succeeded = false;
+
+        for ((&pid, history), outcome) in args.pid.iter().zip(&mut histories).zip(outcomes) {
+            match outcome {
+                Ok(stats) => {
+                    history.push_stats(sampled_at, &stats);
+                    any_succeeded = true;
+                }
+                Err(err) => {
+                    if code == ExitCode::Ok {
+                        code = err.exit_code();
+                    }
+                    let _ = io::stderr().write_all(&format.render_failure(&failure(pid, &err)));
+                }
+            }
+        }
+        taken += 1;
+
+        if !any_succeeded || args.count.is_some_and(|n| taken >= n) {
+            break code;
+        }
+        match interrupted.recv_timeout(interval) {
+            Err(RecvTimeoutError::Timeout) => continue,
+            _ => break code,
+        }
+    };
+
+    for (i, (&pid, history)) in args.pid.iter().zip(&histories).enumerate() {
+        if i > 0 && format == OutputFormat::Table {
+            Output::Stdout.write(b"\n");
+        }
+        Output::Stdout.write(&format.render_summary(&history.summarize(Some(pid))));
     }
     code
 }
 
-fn validate_pid(args: &CliArgs) -> Result<Pid, FshcError> {
-    if (1..=PID_LIMIT).contains(&args.pid) {
-        Ok(args.pid)
-    } else {
-        Err(FshcError::PidOutOfRange)
+fn terminate(outcome: FshcResult, pid: u32, format: OutputFormat, output: &Output) -> ExitCode {
+    match outcome {
+        Ok(stats) => {
+            output.write(&format.render_stats(&stats));
+            ExitCode::Ok
+        }
+        Err(err) => exit(format.render_failure(&failure(pid, &err)), err.exit_code()),
+    }
+}
+
+/// Reports several processes together. A failure to inspect one process
+/// does not prevent the others from being reported, but the exit code
+/// reflects the first failure.
+fn report(
+    pids: &[u32],
+    outcomes: Vec<FshcResult>,
+    format: OutputFormat,
+    output: &Output,
+) -> (ExitCode, bool) {
+    let mut report = Report {
+        processes: Vec::with_capacity(pids.len()),
+        failures: Vec::new(),
+    };
+    let mut code = ExitCode::Ok;
+
+    for (&pid, outcome) in pids.iter().zip(outcomes) {
+        match outcome {
+            Ok(stats) => report.processes.push(stats),
+            Err(err) => {
+                if code == ExitCode::Ok {
+                    code = err.exit_code();
+                }
+                report.failures.push(failure(pid, &err));
+            }
+        }
+    }
+
+    output.write(&format.render_report(&report));
+    if !format.embeds_failures() {
+        for failure in &report.failures {
+            let _ = io::stderr().write_all(&format.render_failure(failure));
+        }
     }
+    (code, !report.processes.is_empty())
+}
+
+fn failure(pid: u32, err: &FshcError) -> Failure {
+    Failure::new(
+        Some(pid),
+        format!(
+            "Failed to obtain file and socket descriptors of process {}",
+            pid
+        ),
+        err,
+    )
+}
+
+fn exit(output: Vec<u8>, code: ExitCode) -> ExitCode {
+    let _ = match code {
+        ExitCode::Ok => io::stdout().write_all(&output),
+        _ => io::stderr().write_all(&output),
+    };
+    code
 }
@@ -1,13 +1,23 @@
 #[cfg(target_os = "linux")]
 use procfs::ProcError;
-use serde::Serialize;
+#[cfg(target_os = "linux")]
+use rustix::io::Errno;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::io;
 use sysexits::ExitCode;
 use thiserror::Error;
 
 pub type Pid = u32;
 
-#[derive(Debug, Serialize)]
+/// The version of the JSON documents fshc produces, see `fshc schema`.
+/// It is bumped when a field is removed or changes its meaning.
+/// New optional fields do not change it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Descriptor stats of one process. Optional fields are only present
+/// when the corresponding report was requested and is supported.
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ProcStats {
     pub pid: Pid,
     pub total_descriptors: u32,
@@ -15,6 +25,18 @@ pub struct ProcStats {
     pub socket_descriptors: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_descriptors: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descriptor_limit: Option<DescriptorLimit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unix_sockets: Option<Vec<UnixSocketGroup>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fd_flags: Option<FdFlagStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub watches: Option<WatchStats>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locks: Option<Vec<FileLock>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mappings: Option<MappingStats>,
 }
 
 impl ProcStats {
@@ -24,14 +46,269 @@ impl ProcStats {
             total_descriptors: 0,
             socket_descriptors: None,
             file_descriptors: None,
+            descriptor_limit: None,
+            unix_sockets: None,
+            fd_flags: None,
+            watches: None,
+            locks: None,
+            mappings: None,
+        }
+    }
+}
+
+/// The `RLIMIT_NOFILE` limit of a process. A missing (`null`) limit means unlimited.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+pub struct DescriptorLimit {
+    pub soft: Option<u64>,
+    pub hard: Option<u64>,
+}
+
+/// Unix domain sockets of a process that share a bound path, a peer and a socket type.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct UnixSocketGroup {
+    /// The bound path, or the abstract name prefixed with `@`.
+    /// `null` for unbound sockets, e.g. the client end of a connection.
+    pub path: Option<String>,
+    /// For unbound sockets, the bound path of the socket they are connected to,
+    /// e.g. the path of the server a client end talks to. `null` if it is not
+    /// bound or cannot be resolved (Linux sock_diag, same network namespace only).
+    pub peer: Option<String>,
+    pub socket_type: UnixSocketType,
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UnixSocketType {
+    Stream,
+    Dgram,
+    SeqPacket,
+    Unknown,
+}
+
+impl UnixSocketType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UnixSocketType::Stream => "stream",
+            UnixSocketType::Dgram => "dgram",
+            UnixSocketType::SeqPacket => "seqpacket",
+            UnixSocketType::Unknown => "unknown",
+        }
+    }
+}
+
+/// How many descriptors of a process use a given access mode or status flag.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct FdFlagStats {
+    pub read_only: u32,
+    pub write_only: u32,
+    pub read_write: u32,
+    pub non_blocking: u32,
+    /// Descriptors that will be inherited by child processes
+    /// because they were opened without `O_CLOEXEC`.
+    pub without_cloexec: u32,
+}
+
+/// epoll and inotify instances of a process and what is registered with them.
+#[derive(Debug, Default, Serialize, JsonSchema)]
+pub struct WatchStats {
+    pub epoll_instances: u32,
+    /// Descriptors registered across all epoll instances
+    pub epoll_watches: u32,
+    pub inotify_instances: u32,
+    pub inotify_watches: u32,
+    /// The `fs.inotify.max_user_watches` limit. Note that it applies to
+    /// all processes of the user, not just the target one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_user_inotify_watches: Option<u64>,
+}
+
+/// A lock held by a process on one of the files it has open.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct FileLock {
+    pub path: String,
+    pub lock_type: FileLockType,
+    pub kind: FileLockKind,
+    pub mode: FileLockMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileLockType {
+    /// A byte-range lock acquired with `fcntl(F_SETLK)`
+    Posix,
+    /// A whole-file lock acquired with `flock(2)`
+    Flock,
+    /// An open file description lock acquired with `fcntl(F_OFD_SETLK)`
+    Ofd,
+    /// Leases and other kinds of locks
+    Other,
+}
+
+impl FileLockType {
+    pub const ALL: [FileLockType; 4] = [
+        FileLockType::Posix,
+        FileLockType::Flock,
+        FileLockType::Ofd,
+        FileLockType::Other,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileLockType::Posix => "posix",
+            FileLockType::Flock => "flock",
+            FileLockType::Ofd => "ofd",
+            FileLockType::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileLockKind {
+    Read,
+    Write,
+    Other,
+}
+
+impl FileLockKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileLockKind::Read => "read",
+            FileLockKind::Write => "write",
+            FileLockKind::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FileLockMode {
+    Advisory,
+    Mandatory,
+    Other,
+}
+
+impl FileLockMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileLockMode::Advisory => "advisory",
+            FileLockMode::Mandatory => "mandatory",
+            FileLockMode::Other => "other",
+        }
+    }
+}
+
+/// Files mapped into the address space of a process. A mapping pins
+/// the file's inode (and disk space) even after its descriptor is closed.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MappingStats {
+    /// The number of distinct mapped files
+    pub mapped_files: u32,
+    /// Mapped files the process has no open descriptor for
+    pub without_descriptor: u32,
+    /// The number of `/proc/<pid>/map_files` entries, one per file-backed
+    /// memory region. Not available without ptrace-level access.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub map_files_entries: Option<u32>,
+    pub files: Vec<MappedFile>,
+}
+
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct MappedFile {
+    pub path: String,
+    /// The number of memory regions backed by this file
+    pub mappings: u32,
+    pub has_descriptor: bool,
+    /// Whether the file has been unlinked while still mapped
+    pub deleted: bool,
+}
+
+/// Why a process, or fshc itself, could not be inspected.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Failure {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<Pid>,
+    pub code: FailureCode,
+    /// The OS error number (`errno`), when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errno: Option<i32>,
+    /// The exit code fshc uses for this failure, see `sysexits(3)`
+    pub exit_code: u8,
+    pub message: String,
+    pub details: String,
+}
+
+impl Failure {
+    pub fn new(pid: Option<Pid>, message: String, err: &FshcError) -> Self {
+        Self {
+            pid,
+            code: err.code(),
+            errno: err.errno(),
+            exit_code: err.exit_code().into(),
+            message,
+            details: err.to_string(),
+        }
+    }
+
+    /// A failure of fshc itself, e.g. to bind a socket, rather than of a query.
+    pub fn io(message: String, err: &io::Error) -> Self {
+        let fshc_err = FshcError::from(err);
+        Self {
+            pid: None,
+            code: fshc_err.code(),
+            errno: fshc_err.errno(),
+            exit_code: err.exit_code().into(),
+            message,
+            details: err.to_string(),
         }
     }
+
+    /// A request of a port or batch session that could not be decoded. It is
+    /// answered instead of ending the session, so that one bad request does not
+    /// take the caller's port down.
+    pub fn invalid_request(details: String) -> Self {
+        Self {
+            pid: None,
+            code: FailureCode::InvalidRequest,
+            errno: None,
+            exit_code: ExitCode::DataErr.into(),
+            message: "Failed to decode request".to_string(),
+            details,
+        }
+    }
+}
+
+/// A stable, machine-readable reason for a failure. Unlike messages,
+/// these values never change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCode {
+    /// The pid is not within the supported range
+    PidOutOfRange,
+    /// There is no such process, or it exited while being inspected
+    NotFound,
+    InvalidInput,
+    /// fshc lacks the privileges to inspect the process
+    PermissionDenied,
+    IoError,
+    /// The requested report is not supported on this platform,
+    /// or by the descriptor source
+    Unsupported,
+    /// An operating system API failed
+    OsError,
+    /// Inspecting the process took longer than the timeout
+    TimedOut,
+    /// A port or batch request could not be decoded
+    InvalidRequest,
+    Other,
 }
 
-#[derive(Debug, Serialize)]
-pub struct Failure<'a> {
-    pub message: &'a str,
-    pub details: &'a str,
+/// The outcome of querying several processes at once.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct Report {
+    pub processes: Vec<ProcStats>,
+    pub failures: Vec<Failure>,
 }
 
 #[derive(Error, Debug)]
@@ -39,18 +316,53 @@ pub enum FshcError {
     #[error("only pid numbers between 1 and 99999 are supported")]
     PidOutOfRange,
     #[error("could not locate a process for the given pid")]
-    InvalidInput,
+    NotFound { errno: Option<i32> },
+    #[error("could not locate a process for the given pid")]
+    InvalidInput { errno: Option<i32> },
     #[error("insufficient permission to inspect file descriptors of the target process")]
-    PermissionDenied,
+    PermissionDenied { errno: Option<i32> },
     #[error("failed to fetch file descriptor details for the target process")]
-    IoError,
+    IoError { errno: Option<i32> },
     #[error("failed to fetch file descriptor details for the target process")]
-    Other,
+    Other { errno: Option<i32> },
+    #[error("this report is not supported on this platform")]
+    Unsupported,
+    #[error("timed out while inspecting the target process")]
+    TimedOut,
     #[cfg(any(target_os = "macos", target_os = "windows"))]
     #[error("{0}")]
     Errno(String),
 }
 
+impl FshcError {
+    pub fn code(&self) -> FailureCode {
+        match self {
+            FshcError::PidOutOfRange => FailureCode::PidOutOfRange,
+            FshcError::NotFound { .. } => FailureCode::NotFound,
+            FshcError::InvalidInput { .. } => FailureCode::InvalidInput,
+            FshcError::PermissionDenied { .. } => FailureCode::PermissionDenied,
+            FshcError::IoError { .. } => FailureCode::IoError,
+            FshcError::Other { .. } => FailureCode::Other,
+            FshcError::Unsupported => FailureCode::Unsupported,
+            FshcError::TimedOut => FailureCode::TimedOut,
+            #[cfg(any(target_os = "macos", target_os = "windows"))]
+            FshcError::Errno(_) => FailureCode::OsError,
+        }
+    }
+
+    /// The OS error number behind this error, if it is known.
+    pub fn errno(&self) -> Option<i32> {
+        match self {
+            FshcError::NotFound { errno }
+            | FshcError::InvalidInput { errno }
+            | FshcError::PermissionDenied { errno }
+            | FshcError::IoError { errno }
+            | FshcError::Other { errno } => *errno,
+            _ => None,
+        }
+    }
+}
+
 pub trait ExitCodeProvider {
     fn exit_code(&self) -> ExitCode {
         ExitCode::DataErr
@@ -61,10 +373,13 @@ impl ExitCodeProvider for FshcError {
     fn exit_code(&self) -> ExitCode {
         match self {
             FshcError::PidOutOfRange => ExitCode::DataErr,
-            FshcError::PermissionDenied => ExitCode::NoPerm,
-            FshcError::IoError => ExitCode::IoErr,
-            FshcError::InvalidInput => ExitCode::DataErr,
-            FshcError::Other => ExitCode::OsErr,
+            FshcError::NotFound { .. } => ExitCode::DataErr,
+            FshcError::PermissionDenied { .. } => ExitCode::NoPerm,
+            FshcError::IoError { .. } => ExitCode::IoErr,
+            FshcError::InvalidInput { .. } => ExitCode::DataErr,
+            FshcError::Other { .. } => ExitCode::OsErr,
+            FshcError::Unsupported => ExitCode::Unavailable,
+            FshcError::TimedOut => ExitCode::TempFail,
             #[cfg(any(target_os = "macos", target_os = "windows"))]
             FshcError::Errno(_) => ExitCode::OsErr,
         }
@@ -97,35 +412,70 @@ impl ExitCodeProvider for ProcError {
 
 pub type FshcResult = Result<ProcStats, FshcError>;
 
-impl From<io::Error> for FshcError {
-    fn from(value: io::Error) -> Self {
+impl From<&io::Error> for FshcError {
+    fn from(value: &io::Error) -> Self {
+        let errno = value.raw_os_error();
         match value.kind() {
-            io::ErrorKind::PermissionDenied => FshcError::PermissionDenied,
-            io::ErrorKind::NotFound => FshcError::InvalidInput,
-            io::ErrorKind::InvalidInput => FshcError::InvalidInput,
-            io::ErrorKind::BrokenPipe => FshcError::IoError,
-            _ => FshcError::Other,
+            io::ErrorKind::PermissionDenied => FshcError::PermissionDenied { errno },
+            io::ErrorKind::NotFound => FshcError::NotFound { errno },
+            io::ErrorKind::InvalidInput => FshcError::InvalidInput { errno },
+            io::ErrorKind::BrokenPipe => FshcError::IoError { errno },
+            _ => FshcError::Other { errno },
         }
     }
 }
 
-#[cfg(any(target_os = "macos", target_os = "windows"))]
+impl From<io::Error> for FshcError {
+    fn from(value: io::Error) -> Self {
+        FshcError::from(&value)
+    }
+}
+
+#[cfg(target_os = "windows")]
 impl From<String> for FshcError {
     fn from(value: String) -> Self {
         FshcError::Errno(value)
     }
 }
 
+/// libproc reports failures as messages like
+/// `return code = 0, errno = 3, message = 'No such process'`.
+#[cfg(target_os = "macos")]
+impl From<String> for FshcError {
+    fn from(value: String) -> Self {
+        // See intro(2)
+        const EPERM: i32 = 1;
+        const ESRCH: i32 = 3;
+
+        let errno = value
+            .split_once("errno = ")
+            .and_then(|(_, rest)| rest.split(',').next())
+            .and_then(|errno| errno.trim().parse().ok());
+        match errno {
+            Some(ESRCH) => FshcError::NotFound { errno },
+            Some(EPERM) => FshcError::PermissionDenied { errno },
+            _ => FshcError::Errno(value),
+        }
+    }
+}
+
 #[cfg(target_os = "linux")]
 impl From<procfs::ProcError> for FshcError {
     fn from(value: procfs::ProcError) -> Self {
         match value {
-            ProcError::PermissionDenied(_) => FshcError::PermissionDenied,
-            ProcError::NotFound(_) => FshcError::InvalidInput,
-            ProcError::Incomplete(_) => FshcError::IoError,
-            ProcError::Io(_, _) => FshcError::IoError,
-            ProcError::Other(_) => FshcError::Other,
-            ProcError::InternalError(_) => FshcError::Other,
+            // procfs maps EACCES and ENOENT to these variants and drops the errno
+            ProcError::PermissionDenied(_) => FshcError::PermissionDenied {
+                errno: Some(Errno::ACCESS.raw_os_error()),
+            },
+            ProcError::NotFound(_) => FshcError::NotFound {
+                errno: Some(Errno::NOENT.raw_os_error()),
+            },
+            ProcError::Incomplete(_) => FshcError::IoError { errno: None },
+            ProcError::Io(err, _) => FshcError::IoError {
+                errno: err.raw_os_error(),
+            },
+            ProcError::Other(_) => FshcError::Other { errno: None },
+            ProcError::InternalError(_) => FshcError::Other { errno: None },
         }
     }
 }
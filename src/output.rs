@@ -0,0 +1,38 @@
+use std::io::{self, Write};
+use std::net::{SocketAddr, UdpSocket};
+
+/// Where the stats are written to. Failures always go to standard error.
+pub enum Output {
+    Stdout,
+    /// Sends every line as a separate datagram to a StatsD agent
+    Statsd(UdpSocket),
+}
+
+impl Output {
+    pub fn statsd(addr: SocketAddr) -> io::Result<Self> {
+        let local: SocketAddr = if addr.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(addr)?;
+        Ok(Output::Statsd(socket))
+    }
+
+    pub fn write(&self, rendered: &[u8]) {
+        match self {
+            Output::Stdout => {
+                let mut stdout = io::stdout().lock();
+                let _ = stdout.write_all(rendered);
+                let _ = stdout.flush();
+            }
+            Output::Statsd(socket) => {
+                // StatsD is fire-and-forget, a missing agent must not stop sampling
+                for line in rendered.split(|b| *b == b'\n').filter(|l| !l.is_empty()) {
+                    let _ = socket.send(line);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::io::{self, Read, Write};
+
+use sysexits::ExitCode;
+
+use fshc::outcome::*;
+
+use crate::cli::PortArgs;
+use crate::etf;
+use crate::format::OutputFormat;
+use crate::request::Request;
+
+/// Requests are small maps, anything larger is a corrupt or hostile frame.
+const MAX_REQUEST_LEN: u64 = 64 * 1024;
+
+/// Runs as an Erlang port opened with `{packet, 4}`: every request and response
+/// is preceded by its length as a 4 byte big-endian integer. Requests are
+/// encoded with `term_to_binary/1` or as JSON. The port exits when standard
+/// input is closed, that is, when the owning Erlang process terminates.
+pub fn run(args: &PortArgs) -> ExitCode {
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+
+    loop {
+        let packet = match read_packet(&mut stdin) {
+            Ok(Some(packet)) => packet,
+            Ok(None) => return ExitCode::Ok,
+            Err(err) => return fail("Failed to read a request", &err, args.format),
+        };
+
+        let response = match packet.and_then(|payload| decode(&payload)) {
+            Ok(request) => request.respond(args.format),
+            Err(failure) => args.format.render_failure(&failure),
+        };
+
+        if let Err(err) = write_packet(&mut stdout, &response) {
+            return fail("Failed to write a response", &err, args.format);
+        }
+    }
+}
+
+fn decode(payload: &[u8]) -> Result<Request, Failure> {
+    let value = if payload.first() == Some(&etf::VERSION) {
+        etf::decode(payload).map_err(Failure::invalid_request)?
+    } else {
+        serde_json::from_slice(payload).map_err(|err| Failure::invalid_request(err.to_string()))?
+    };
+    Request::from_json(value)
+}
+
+/// Returns `None` when standard input was closed between two requests.
+/// A payload longer than [`MAX_REQUEST_LEN`] is skipped without being buffered
+/// and answered with a failure, so that the next request is still read in sync.
+fn read_packet(input: &mut impl Read) -> io::Result<Option<Result<Vec<u8>, Failure>>> {
+    let mut len = [0u8; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let len = u32::from_be_bytes(len);
+
+    if u64::from(len) > MAX_REQUEST_LEN {
+        let skipped = io::copy(&mut input.take(len.into()), &mut io::sink())?;
+        if skipped < u64::from(len) {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        return Ok(Some(Err(Failure::invalid_request(format!(
+            "the request is {} bytes long, at most {} bytes are accepted",
+            len, MAX_REQUEST_LEN
+        )))));
+    }
+
+    let mut payload = vec![0u8; len as usize];
+    input.read_exact(&mut payload)?;
+    Ok(Some(Ok(payload)))
+}
+
+fn write_packet(output: &mut impl Write, payload: &[u8]) -> io::Result<()> {
+    let len = u32::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "response is too large"))?;
+    output.write_all(&len.to_be_bytes())?;
+    output.write_all(payload)?;
+    output.flush()
+}
+
+/// Standard output belongs to the framing protocol, so I/O failures of the
+/// port itself are reported on standard error.
+fn fail(message: &str, err: &io::Error, format: OutputFormat) -> ExitCode {
+    let failure = Failure::io(message.to_string(), err);
+    let _ = io::stderr().write_all(&format.render_failure(&failure));
+    err.exit_code()
+}
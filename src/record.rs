@@ -0,0 +1,290 @@
+//! A flight recorder: samples are written to a file of a fixed size that wraps
+//! around, so that the most recent history survives without a time series database.
+//!
+//! The file starts with a header (magic, format version, capacity, next slot,
+//! number of samples) followed by `capacity` fixed-size slots. All integers are
+//! little-endian.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::thread;
+use std::time::SystemTime;
+
+use clap::CommandFactory;
+use clap::error::ErrorKind;
+use fshc::outcome::*;
+use fshc::{QueryOptions, count_descriptors};
+use serde::Serialize;
+use sysexits::ExitCode;
+
+use crate::cli::{CliArgs, RecordArgs, ReplayArgs};
+use crate::format::OutputFormat;
+use crate::summary::History;
+use crate::time::{format_timestamp, unix_timestamp};
+
+const MAGIC: &[u8; 8] = b"FSHCRING";
+const FORMAT_VERSION: u32 = 1;
+const HEADER_LEN: u64 = 24;
+const SLOT_LEN: u64 = 24;
+/// Stored in place of counts that were not collected
+const MISSING: u32 = u32::MAX;
+
+/// A recorded sample.
+#[derive(Debug, Serialize)]
+pub struct Sample {
+    pub pid: Pid,
+    /// An RFC 3339 timestamp
+    pub sampled_at: String,
+    pub total_descriptors: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_descriptors: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_descriptors: Option<u32>,
+}
+
+pub fn record(args: &RecordArgs) -> ExitCode {
+    let mut recording = match Recording::open(&args.file, args.capacity) {
+        Ok(recording) => recording,
+        Err(err) => return inaccessible(&args.file, &err, OutputFormat::Json),
+    };
+
+    let mut taken = 0;
+    loop {
+        let stats = match count_descriptors(args.pid, &QueryOptions::default()) {
+            Ok(stats) => stats,
+            Err(err) => {
+                let failure = crate::failure(args.pid, &err);
+                return crate::exit(OutputFormat::Json.render_failure(&failure), err.exit_code());
+            }
+        };
+        let slot = Slot {
+            sampled_at: unix_timestamp(SystemTime::now()),
+            pid: stats.pid,
+            total_descriptors: stats.total_descriptors,
+            socket_descriptors: stats.socket_descriptors.unwrap_or(MISSING),
+            file_descriptors: stats.file_descriptors.unwrap_or(MISSING),
+        };
+        if let Err(err) = recording.append(&slot) {
+            return inaccessible(&args.file, &err, OutputFormat::Json);
+        }
+        taken += 1;
+
+        if args.count.is_some_and(|n| taken >= n) {
+            return ExitCode::Ok;
+        }
+        thread::sleep(args.interval);
+    }
+}
+
+pub fn replay(args: &ReplayArgs) -> ExitCode {
+    let format = match args.format.unwrap_or_else(OutputFormat::detect) {
+        format @ (OutputFormat::Json | OutputFormat::Table) => format,
+        _ => CliArgs::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "replay only supports --format json and --format table",
+            )
+            .exit(),
+    };
+
+    let slots = match File::open(&args.file).and_then(Recording::read) {
+        Ok(slots) => slots,
+        Err(err) => return inaccessible(&args.file, &err, format),
+    };
+
+    if !args.summary {
+        let samples: Vec<Sample> = slots.iter().map(Slot::to_sample).collect();
+        return crate::exit(format.render_samples(&samples), ExitCode::Ok);
+    }
+
+    let mut history = History::default();
+    for slot in &slots {
+        history.push(
+            slot.sampled_at,
+            slot.total_descriptors,
+            recorded(slot.socket_descriptors),
+            recorded(slot.file_descriptors),
+        );
+    }
+    crate::exit(
+        format.render_summary(&history.summarize(None)),
+        ExitCode::Ok,
+    )
+}
+
+fn inaccessible(path: &Path, err: &io::Error, format: OutputFormat) -> ExitCode {
+    let failure = Failure::io(
+        format!("Failed to access recording {}", path.display()),
+        err,
+    );
+    crate::exit(format.render_failure(&failure), err.exit_code())
+}
+
+/// A sample as it is stored on disk.
+struct Slot {
+    sampled_at: u64,
+    pid: Pid,
+    total_descriptors: u32,
+    socket_descriptors: u32,
+    file_descriptors: u32,
+}
+
+impl Slot {
+    fn encode(&self) -> [u8; SLOT_LEN as usize] {
+        let mut bytes = [0; SLOT_LEN as usize];
+        bytes[0..8].copy_from_slice(&self.sampled_at.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.pid.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.total_descriptors.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.socket_descriptors.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.file_descriptors.to_le_bytes());
+        bytes
+    }
+
+    fn decode(bytes: &[u8; SLOT_LEN as usize]) -> Self {
+        Slot {
+            sampled_at: u64::from_le_bytes(bytes[0..8].try_into().unwrap()),
+            pid: u32_at(bytes, 8),
+            total_descriptors: u32_at(bytes, 12),
+            socket_descriptors: u32_at(bytes, 16),
+            file_descriptors: u32_at(bytes, 20),
+        }
+    }
+
+    fn to_sample(&self) -> Sample {
+        Sample {
+            pid: self.pid,
+            sampled_at: format_timestamp(self.sampled_at),
+            total_descriptors: self.total_descriptors,
+            socket_descriptors: recorded(self.socket_descriptors),
+            file_descriptors: recorded(self.file_descriptors),
+        }
+    }
+}
+
+/// An open recording, see the module documentation for its layout.
+struct Recording {
+    file: File,
+    capacity: u32,
+    /// The slot the next sample is written to
+    next: u32,
+    /// How many slots are in use, at most `capacity`
+    len: u32,
+}
+
+impl Recording {
+    /// Opens the recording at `path`, or creates it with room for `capacity` samples.
+    /// The capacity of an existing recording is kept.
+    fn open(path: &Path, capacity: u32) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        if file.metadata()?.len() > 0 {
+            return Self::from_file(file);
+        }
+        let mut recording = Recording {
+            file,
+            capacity,
+            next: 0,
+            len: 0,
+        };
+        recording.write_header()?;
+        recording
+            .file
+            .set_len(HEADER_LEN + SLOT_LEN * u64::from(capacity))?;
+        Ok(recording)
+    }
+
+    /// Reads every sample of a recording, oldest first.
+    fn read(file: File) -> io::Result<Vec<Slot>> {
+        let mut recording = Self::from_file(file)?;
+        let (newest, oldest) = if recording.len < recording.capacity {
+            (0..recording.len, 0..0)
+        } else {
+            (0..recording.next, recording.next..recording.capacity)
+        };
+
+        let mut slots = Vec::with_capacity(recording.len as usize);
+        for index in oldest.chain(newest) {
+            recording.seek_to(index)?;
+            let mut bytes = [0; SLOT_LEN as usize];
+            recording.file.read_exact(&mut bytes)?;
+            slots.push(Slot::decode(&bytes));
+        }
+        Ok(slots)
+    }
+
+    fn from_file(mut file: File) -> io::Result<Self> {
+        let mut header = [0; HEADER_LEN as usize];
+        file.read_exact(&mut header)
+            .map_err(|_| not_a_recording())?;
+        if &header[0..8] != MAGIC {
+            return Err(not_a_recording());
+        }
+        let version = u32_at(&header, 8);
+        if version != FORMAT_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported recording format version {}", version),
+            ));
+        }
+
+        let recording = Recording {
+            file,
+            capacity: u32_at(&header, 12),
+            next: u32_at(&header, 16),
+            len: u32_at(&header, 20),
+        };
+        if recording.capacity == 0
+            || recording.next >= recording.capacity
+            || recording.len > recording.capacity
+        {
+            return Err(not_a_recording());
+        }
+        Ok(recording)
+    }
+
+    /// Writes the sample before updating the header, so that an interrupted
+    /// write loses at most the oldest sample.
+    fn append(&mut self, slot: &Slot) -> io::Result<()> {
+        self.seek_to(self.next)?;
+        self.file.write_all(&slot.encode())?;
+
+        self.next = (self.next + 1) % self.capacity;
+        self.len = (self.len + 1).min(self.capacity);
+        self.write_header()
+    }
+
+    fn write_header(&mut self) -> io::Result<()> {
+        let mut header = [0; HEADER_LEN as usize];
+        header[0..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&FORMAT_VERSION.to_le_bytes());
+        header[12..16].copy_from_slice(&self.capacity.to_le_bytes());
+        header[16..20].copy_from_slice(&self.next.to_le_bytes());
+        header[20..24].copy_from_slice(&self.len.to_le_bytes());
+
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.write_all(&header)
+    }
+
+    fn seek_to(&mut self, index: u32) -> io::Result<()> {
+        let offset = HEADER_LEN + SLOT_LEN * u64::from(index);
+        self.file.seek(SeekFrom::Start(offset)).map(|_| ())
+    }
+}
+
+fn recorded(count: u32) -> Option<u32> {
+    Some(count).filter(|n| *n != MISSING)
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+fn not_a_recording() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "not an fshc recording")
+}
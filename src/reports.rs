@@ -0,0 +1,34 @@
+//! Reports that are built from the raw inputs of a [`DescriptorSource`],
+//! independently of the platform the source reads them from.
+
+mod fdinfo;
+mod locks;
+mod maps;
+mod unix_sockets;
+
+pub(crate) use fdinfo::{fd_flags, watches};
+pub(crate) use locks::locks;
+pub(crate) use maps::mappings;
+pub(crate) use unix_sockets::unix_sockets;
+
+use crate::outcome::*;
+use crate::source::{DescriptorSource, DescriptorTarget};
+
+/// Splits the first `n` whitespace separated fields off a procfs row.
+/// Returns them with the rest of the row, which may contain whitespace,
+/// or `None` if the row has fewer fields.
+fn fields(row: &str, n: usize) -> Option<(Vec<&str>, &str)> {
+    let mut fields = Vec::with_capacity(n);
+    let mut rest = row.trim_start();
+
+    for _ in 0..n {
+        if rest.is_empty() {
+            return None;
+        }
+        let (field, remainder) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        fields.push(field);
+        rest = remainder.trim_start();
+    }
+
+    Some((fields, rest.trim_end()))
+}
@@ -0,0 +1,90 @@
+use super::*;
+
+// See open(2) and fcntl(2). These are the generic Linux values
+// used by x86, x86_64, ARM and AArch64.
+const O_ACCMODE: u32 = 0o3;
+const O_RDONLY: u32 = 0o0;
+const O_WRONLY: u32 = 0o1;
+const O_RDWR: u32 = 0o2;
+const O_NONBLOCK: u32 = 0o4000;
+const O_CLOEXEC: u32 = 0o2000000;
+
+const EVENTPOLL_ANON_INODE: &str = "[eventpoll]";
+const INOTIFY_ANON_INODE: &str = "inotify";
+
+/// Summarizes the access mode and status flags of every descriptor
+/// of a process using its fdinfo.
+pub(crate) fn fd_flags(source: &impl DescriptorSource, pid: Pid) -> Result<FdFlagStats, FshcError> {
+    let mut stats = FdFlagStats::default();
+
+    for descriptor in source.descriptors(pid)? {
+        let Some(content) = source.fdinfo(pid, descriptor.fd)? else {
+            continue;
+        };
+        if let Some(flags) = parse_flags(&content) {
+            stats.add(flags);
+        }
+    }
+
+    Ok(stats)
+}
+
+/// Counts epoll and inotify instances of a process and the number
+/// of descriptors and watches registered with them.
+pub(crate) fn watches(source: &impl DescriptorSource, pid: Pid) -> Result<WatchStats, FshcError> {
+    let mut stats = WatchStats::default();
+
+    for descriptor in source.descriptors(pid)? {
+        let DescriptorTarget::AnonInode(kind) = descriptor.target else {
+            continue;
+        };
+        if kind != EVENTPOLL_ANON_INODE && kind != INOTIFY_ANON_INODE {
+            continue;
+        }
+        let Some(content) = source.fdinfo(pid, descriptor.fd)? else {
+            continue;
+        };
+        if kind == EVENTPOLL_ANON_INODE {
+            stats.epoll_instances += 1;
+            stats.epoll_watches += count_lines_with_prefix(&content, "tfd:");
+        } else {
+            stats.inotify_instances += 1;
+            stats.inotify_watches += count_lines_with_prefix(&content, "inotify wd:");
+        }
+    }
+    stats.max_user_inotify_watches = source.max_user_inotify_watches();
+
+    Ok(stats)
+}
+
+fn count_lines_with_prefix(content: &str, prefix: &str) -> u32 {
+    content
+        .lines()
+        .filter(|line| line.starts_with(prefix))
+        .count() as u32
+}
+
+/// Extracts the octal `flags:` field of an fdinfo file.
+fn parse_flags(content: &str) -> Option<u32> {
+    content
+        .lines()
+        .find_map(|line| line.strip_prefix("flags:"))
+        .and_then(|value| u32::from_str_radix(value.trim(), 8).ok())
+}
+
+impl FdFlagStats {
+    fn add(&mut self, flags: u32) {
+        match flags & O_ACCMODE {
+            O_RDONLY => self.read_only += 1,
+            O_WRONLY => self.write_only += 1,
+            O_RDWR => self.read_write += 1,
+            _ => (),
+        }
+        if flags & O_NONBLOCK != 0 {
+            self.non_blocking += 1;
+        }
+        if flags & O_CLOEXEC == 0 {
+            self.without_cloexec += 1;
+        }
+    }
+}
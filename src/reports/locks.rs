@@ -0,0 +1,72 @@
+use super::*;
+
+use std::collections::HashSet;
+
+/// Lists the file locks that are held by a process on the files it has
+/// open, using the `lock:` lines of the fdinfo of every file descriptor.
+///
+/// The kernel only lists the locks of the open file a descriptor refers to
+/// that belong to the process or to that open file. This covers OFD locks
+/// and `flock` locks inherited across `fork()`, which `/proc/locks`
+/// attributes to whichever process created them.
+pub(crate) fn locks(source: &impl DescriptorSource, pid: Pid) -> Result<Vec<FileLock>, FshcError> {
+    let mut locks = Vec::new();
+    // descriptors that share an open file, e.g. after dup(2), list the same locks
+    let mut seen = HashSet::new();
+
+    for descriptor in source.descriptors(pid)? {
+        let DescriptorTarget::File(path) = descriptor.target else {
+            continue;
+        };
+        let Some(content) = source.fdinfo(pid, descriptor.fd)? else {
+            continue;
+        };
+        for row in content
+            .lines()
+            .filter_map(|line| line.strip_prefix("lock:"))
+        {
+            let Some((lock, status)) = parse_row(row, &path) else {
+                continue;
+            };
+            if seen.insert((path.clone(), status.to_string())) {
+                locks.push(lock);
+            }
+        }
+    }
+
+    Ok(locks)
+}
+
+/// Parses a lock in the format of `/proc/locks`:
+/// `Id: Type Mode Kind Pid Major:Minor:Inode Start End`. Returns the lock
+/// and the row without its id, which is only unique within one fdinfo file.
+/// Rows of processes waiting for a lock, marked with `->`, are skipped.
+fn parse_row<'a>(row: &'a str, path: &str) -> Option<(FileLock, &'a str)> {
+    let (_, status) = row.trim().split_once(char::is_whitespace)?;
+    let (fields, _) = fields(status, 3)?;
+    if fields[0] == "->" {
+        return None;
+    }
+
+    let lock = FileLock {
+        path: path.to_string(),
+        lock_type: match fields[0] {
+            "POSIX" => FileLockType::Posix,
+            "FLOCK" => FileLockType::Flock,
+            "OFDLCK" => FileLockType::Ofd,
+            _ => FileLockType::Other,
+        },
+        mode: match fields[1] {
+            "ADVISORY" => FileLockMode::Advisory,
+            "MANDATORY" => FileLockMode::Mandatory,
+            _ => FileLockMode::Other,
+        },
+        kind: match fields[2] {
+            "READ" => FileLockKind::Read,
+            "WRITE" => FileLockKind::Write,
+            _ => FileLockKind::Other,
+        },
+    };
+
+    Some((lock, status.trim_start()))
+}
@@ -0,0 +1,72 @@
+use super::*;
+
+use std::collections::{BTreeMap, HashSet};
+
+const DELETED_SUFFIX: &str = " (deleted)";
+
+/// Lists the distinct files mapped into the address space of a process,
+/// including the ones it no longer has a descriptor for.
+///
+/// Mapped files are matched with descriptors by path and inode. The device
+/// numbers of maps and stat(2) differ for files on btrfs subvolumes.
+pub(crate) fn mappings(
+    source: &impl DescriptorSource,
+    pid: Pid,
+) -> Result<MappingStats, FshcError> {
+    let open_files = open_files(source, pid)?;
+
+    let mut files: BTreeMap<(String, u64), u32> = BTreeMap::new();
+    for line in source.maps(pid)? {
+        if let Some((path, inode)) = parse_line(&line) {
+            *files.entry((path.to_string(), inode)).or_default() += 1;
+        }
+    }
+
+    let files: Vec<MappedFile> = files
+        .into_iter()
+        .map(|((path, inode), mappings)| MappedFile {
+            has_descriptor: open_files.contains(&(path.clone(), inode)),
+            deleted: path.ends_with(DELETED_SUFFIX),
+            path,
+            mappings,
+        })
+        .collect();
+
+    Ok(MappingStats {
+        mapped_files: files.len() as u32,
+        without_descriptor: files.iter().filter(|f| !f.has_descriptor).count() as u32,
+        map_files_entries: source.map_files_entries(pid),
+        files,
+    })
+}
+
+/// The path and inode of every file a process has an open descriptor for.
+fn open_files(
+    source: &impl DescriptorSource,
+    pid: Pid,
+) -> Result<HashSet<(String, u64)>, FshcError> {
+    let mut files = HashSet::new();
+
+    for descriptor in source.descriptors(pid)? {
+        let DescriptorTarget::File(path) = descriptor.target else {
+            continue;
+        };
+        if let Some(inode) = source.inode(pid, descriptor.fd)? {
+            files.insert((path, inode));
+        }
+    }
+
+    Ok(files)
+}
+
+/// Parses a line of `/proc/<pid>/maps`:
+/// `Address Perms Offset Major:Minor Inode Path`. Returns `None` for
+/// mappings that are not backed by a file, e.g. `[heap]` or anonymous ones.
+fn parse_line(line: &str) -> Option<(&str, u64)> {
+    let (fields, path) = fields(line, 5)?;
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    Some((path, fields[4].parse().ok()?))
+}
@@ -0,0 +1,85 @@
+use super::*;
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// See socket(2), these values are stable across all Linux architectures
+// that fshc is built for.
+const SOCK_STREAM: u16 = 1;
+const SOCK_DGRAM: u16 = 2;
+const SOCK_SEQPACKET: u16 = 5;
+
+/// Resolves the socket descriptors of a process against the Unix domain
+/// socket table of its network namespace and groups them by bound path,
+/// peer and socket type. Unbound sockets are grouped by the bound path of
+/// their peer, if the source knows it.
+pub(crate) fn unix_sockets(
+    source: &impl DescriptorSource,
+    pid: Pid,
+) -> Result<Vec<UnixSocketGroup>, FshcError> {
+    let inodes: HashSet<u64> = source
+        .descriptors(pid)?
+        .into_iter()
+        .filter_map(|descriptor| match descriptor.target {
+            DescriptorTarget::Socket(inode) => Some(inode),
+            _ => None,
+        })
+        .collect();
+
+    // the peers of the sockets of the process are usually owned by other processes
+    let table: HashMap<u64, (UnixSocketType, Option<String>)> = source
+        .unix_socket_table(pid)?
+        .iter()
+        .filter_map(|row| parse_row(row))
+        .map(|(inode, socket_type, path)| (inode, (socket_type, path)))
+        .collect();
+    let peers = source.unix_socket_peers(pid).unwrap_or_default();
+
+    let mut groups: BTreeMap<(Option<String>, Option<String>, UnixSocketType), u32> =
+        BTreeMap::new();
+    for (inode, (socket_type, path)) in &table {
+        if !inodes.contains(inode) {
+            continue;
+        }
+        let peer = match path {
+            Some(_) => None,
+            None => peers
+                .get(inode)
+                .and_then(|peer| table.get(peer))
+                .and_then(|(_, peer_path)| peer_path.clone()),
+        };
+        *groups
+            .entry((path.clone(), peer, *socket_type))
+            .or_default() += 1;
+    }
+
+    Ok(groups
+        .into_iter()
+        .map(|((path, peer, socket_type), count)| UnixSocketGroup {
+            path,
+            peer,
+            socket_type,
+            count,
+        })
+        .collect())
+}
+
+/// Parses a row of `/proc/net/unix`:
+/// `Num RefCount Protocol Flags Type St Inode Path`, where the path is
+/// missing for unbound sockets and starts with `@` for abstract ones.
+fn parse_row(row: &str) -> Option<(u64, UnixSocketType, Option<String>)> {
+    let (fields, path) = fields(row, 7)?;
+    let socket_type = u16::from_str_radix(fields[4], 16).ok()?;
+    let inode = fields[6].parse().ok()?;
+    let path = (!path.is_empty()).then(|| path.to_string());
+
+    Some((inode, to_socket_type(socket_type), path))
+}
+
+fn to_socket_type(value: u16) -> UnixSocketType {
+    match value {
+        SOCK_STREAM => UnixSocketType::Stream,
+        SOCK_DGRAM => UnixSocketType::Dgram,
+        SOCK_SEQPACKET => UnixSocketType::SeqPacket,
+        _ => UnixSocketType::Unknown,
+    }
+}
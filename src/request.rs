@@ -0,0 +1,28 @@
+use fshc::QueryOptions;
+use fshc::outcome::*;
+use serde::Deserialize;
+
+use crate::format::OutputFormat;
+
+/// A query sent to a long-running port or batch session, e.g.
+/// `#{pid => 123, only_total => true}` or `{"pid": 123, "only_total": true}`.
+#[derive(Debug, Deserialize)]
+pub struct Request {
+    pub pid: u32,
+    #[serde(flatten)]
+    pub query: QueryOptions,
+}
+
+impl Request {
+    pub fn from_json(value: serde_json::Value) -> Result<Self, Failure> {
+        serde_json::from_value(value).map_err(|err| Failure::invalid_request(err.to_string()))
+    }
+
+    /// Runs the query and renders either the stats or the failure.
+    pub fn respond(&self, format: OutputFormat) -> Vec<u8> {
+        match fshc::count_descriptors(self.pid, &self.query) {
+            Ok(stats) => format.render_stats(&stats),
+            Err(err) => format.render_failure(&crate::failure(self.pid, &err)),
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Querying many processes on a bounded number of worker threads.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+
+use crate::source::{DescriptorSource, System};
+use crate::{FshcError, FshcResult, Pid, QueryOptions, count_descriptors_with};
+
+/// Queries up to `jobs` processes at a time, each on a thread of its own.
+/// A query that takes longer than `timeout` fails with [`FshcError::TimedOut`],
+/// so that one process stuck in a `/proc` read (e.g. in D state on NFS) does
+/// not stall the whole scan.
+///
+/// A timed out query cannot be interrupted: its thread is left behind and its
+/// result is discarded whenever it completes. Until then, later scans report
+/// the process as timed out without querying it again, so a process that
+/// stays stuck costs one thread rather than one per scan.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    jobs: usize,
+    timeout: Option<Duration>,
+    /// The process each query thread is querying, shared by clones of the scanner
+    running: Arc<Mutex<HashMap<ThreadId, Pid>>>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Self::new(1, None)
+    }
+}
+
+impl Scanner {
+    pub fn new(jobs: usize, timeout: Option<Duration>) -> Self {
+        Self {
+            jobs,
+            timeout,
+            running: Arc::default(),
+        }
+    }
+
+    /// Returns the results in the order of `pids`.
+    pub fn scan(&self, pids: &[Pid], options: &QueryOptions) -> Vec<FshcResult> {
+        self.scan_with(Arc::new(System), pids, options)
+    }
+
+    pub fn scan_with<S>(
+        &self,
+        source: Arc<S>,
+        pids: &[Pid],
+        options: &QueryOptions,
+    ) -> Vec<FshcResult>
+    where
+        S: DescriptorSource + Send + Sync + 'static,
+    {
+        if self.jobs <= 1 && self.timeout.is_none() {
+            return pids
+                .iter()
+                .map(|&pid| count_descriptors_with(&*source, pid, options))
+                .collect();
+        }
+
+        let options = Arc::new(options.clone());
+        let (sender, receiver) = mpsc::channel();
+        let mut results: Vec<Option<FshcResult>> = pids.iter().map(|_| None).collect();
+        // the deadlines and threads of the queries in flight, by their index in `pids`
+        let mut in_flight: BTreeMap<usize, (Option<Instant>, ThreadId)> = BTreeMap::new();
+        let mut next = 0;
+
+        loop {
+            while in_flight.len() < self.jobs.max(1) && next < pids.len() {
+                let (index, pid) = (next, pids[next]);
+                next += 1;
+                // holding the lock keeps the thread from finishing before it is registered
+                let mut running = self.running();
+                let abandoned = running
+                    .iter()
+                    .any(|(id, &p)| p == pid && !in_flight.values().any(|(_, t)| t == id));
+                if abandoned {
+                    results[index] = Some(Err(FshcError::TimedOut));
+                    continue;
+                }
+
+                let (source, options, sender) = (source.clone(), options.clone(), sender.clone());
+                let scanner = self.clone();
+                let spawned = thread::Builder::new()
+                    .name(format!("fshc-scan-{pid}"))
+                    .spawn(move || {
+                        let result = count_descriptors_with(&*source, pid, &options);
+                        scanner.running().remove(&thread::current().id());
+                        // the scan is gone if the query has timed out
+                        let _ = sender.send((index, result));
+                    });
+                match spawned {
+                    Ok(handle) => {
+                        let id = handle.thread().id();
+                        running.insert(id, pid);
+                        in_flight.insert(index, (self.timeout.map(|t| Instant::now() + t), id));
+                    }
+                    Err(err) => results[index] = Some(Err(FshcError::from(err))),
+                }
+            }
+
+            if in_flight.is_empty() {
+                break;
+            }
+            let deadline = in_flight.values().filter_map(|(d, _)| *d).min();
+
+            let received = match deadline {
+                Some(deadline) => {
+                    receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok((index, result)) => {
+                    if in_flight.remove(&index).is_some() {
+                        results[index] = Some(result);
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    let now = Instant::now();
+                    in_flight.retain(|&index, (deadline, _)| match deadline {
+                        Some(deadline) if *deadline <= now => {
+                            results[index] = Some(Err(FshcError::TimedOut));
+                            false
+                        }
+                        _ => true,
+                    });
+                }
+                // cannot happen while the scan holds a sender
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        results
+            .into_iter()
+            .map(|result| result.unwrap_or(Err(FshcError::Other { errno: None })))
+            .collect()
+    }
+
+    fn running(&self) -> MutexGuard<'_, HashMap<ThreadId, Pid>> {
+        // the map stays consistent even if a query thread panicked
+        self.running.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
@@ -0,0 +1,40 @@
+use schemars::SchemaGenerator;
+use schemars::generate::SchemaSettings;
+use serde_json::{Value, json};
+
+use fshc::outcome::*;
+
+/// Renders a JSON Schema that describes every document of the JSON output:
+/// the stats of a single process, a failure, or the report for several processes.
+/// Only top-level documents carry a `schema_version`.
+pub fn render() -> Vec<u8> {
+    let mut generator = SchemaGenerator::new(SchemaSettings::draft2020_12());
+    let documents: Vec<Value> = [
+        generator.subschema_for::<ProcStats>(),
+        generator.subschema_for::<Failure>(),
+        generator.subschema_for::<Report>(),
+    ]
+    .into_iter()
+    .map(|schema| {
+        json!({
+            "allOf": [schema],
+            "properties": {
+                "schema_version": { "const": SCHEMA_VERSION }
+            },
+            "required": ["schema_version"]
+        })
+    })
+    .collect();
+
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "fshc JSON output",
+        "oneOf": documents,
+        "$defs": generator.take_definitions(true),
+    });
+
+    let mut bytes = serde_json::to_vec_pretty(&schema)
+        .unwrap_or_else(|err| panic!("could not serialize the schema: {}", err));
+    bytes.push(b'\n');
+    bytes
+}
@@ -0,0 +1,131 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use sysexits::ExitCode;
+
+use fshc::QueryOptions;
+use fshc::outcome::*;
+
+use crate::cli::ServeArgs;
+use crate::format::OutputFormat;
+
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+const OPENMETRICS_CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+/// Runs a minimal HTTP exporter that serves the descriptor stats of the target
+/// process on `/metrics`. Scrapes are infrequent, so connections are handled
+/// one at a time.
+pub fn run(args: &ServeArgs) -> ExitCode {
+    let listener = match TcpListener::bind(args.listen) {
+        Ok(listener) => listener,
+        Err(err) => {
+            let failure = Failure::io(format!("Failed to listen on {}", args.listen), &err);
+            return crate::exit(OutputFormat::Json.render_failure(&failure), err.exit_code());
+        }
+    };
+
+    if let Ok(addr) = listener.local_addr() {
+        eprintln!("Serving metrics on http://{}/metrics", addr);
+    }
+
+    for stream in listener.incoming().flatten() {
+        // a misbehaving client must not take the exporter down
+        let _ = handle(stream, args);
+    }
+
+    ExitCode::Ok
+}
+
+fn handle(mut stream: TcpStream, args: &ServeArgs) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    let mut accepts_openmetrics = false;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("accept")
+            && value.contains("application/openmetrics-text")
+        {
+            accepts_openmetrics = true;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default();
+    let path = path.split('?').next().unwrap_or_default();
+
+    let response = match (method, path) {
+        ("GET", "/metrics") => metrics(args, accepts_openmetrics),
+        ("GET", "/") => Response {
+            status: "200 OK",
+            content_type: "text/plain; charset=utf-8",
+            body: b"fshc exporter, metrics are served on /metrics\n".to_vec(),
+        },
+        ("GET", _) => Response {
+            status: "404 Not Found",
+            content_type: "text/plain; charset=utf-8",
+            body: b"Not Found\n".to_vec(),
+        },
+        _ => Response {
+            status: "405 Method Not Allowed",
+            content_type: "text/plain; charset=utf-8",
+            body: b"Method Not Allowed\n".to_vec(),
+        },
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len(),
+    )?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+fn metrics(args: &ServeArgs, openmetrics: bool) -> Response {
+    let query = QueryOptions {
+        limit: true,
+        ..QueryOptions::from(&args.query)
+    };
+    match fshc::count_descriptors(args.pid, &query) {
+        Ok(stats) => {
+            let mut body = OutputFormat::Prometheus.render_stats(&stats);
+            if openmetrics {
+                body.extend_from_slice(b"# EOF\n");
+            }
+            Response {
+                status: "200 OK",
+                content_type: if openmetrics {
+                    OPENMETRICS_CONTENT_TYPE
+                } else {
+                    PROMETHEUS_CONTENT_TYPE
+                },
+                body,
+            }
+        }
+        // a failed scrape is reported as such to Prometheus via the `up` metric
+        Err(err) => Response {
+            status: "500 Internal Server Error",
+            content_type: "application/json",
+            body: OutputFormat::Json.render_failure(&crate::failure(args.pid, &err)),
+        },
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::SystemTime;
+
+use clap::CommandFactory;
+use clap::error::ErrorKind;
+use fshc::outcome::*;
+use fshc::source::{Descriptor, DescriptorSource, System};
+use fshc::validate_pid;
+use serde::{Deserialize, Serialize};
+use sysexits::ExitCode;
+
+use crate::cli::{CliArgs, DiffArgs, SnapshotArgs};
+use crate::format::OutputFormat;
+use crate::time::{format_timestamp, unix_timestamp};
+
+/// The version of the snapshot file format. It is independent of
+/// [`SCHEMA_VERSION`] and is bumped only when snapshots stop being comparable.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Every descriptor of a process at a point in time.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub snapshot_version: u32,
+    pub pid: Pid,
+    /// An RFC 3339 timestamp
+    pub taken_at: String,
+    pub descriptors: Vec<Descriptor>,
+}
+
+/// Descriptors opened and closed between two snapshots, grouped by type and path.
+#[derive(Debug, Serialize)]
+pub struct SnapshotDiff {
+    pub before: SnapshotInfo,
+    pub after: SnapshotInfo,
+    pub opened: Vec<DiffGroup>,
+    pub closed: Vec<DiffGroup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnapshotInfo {
+    pub pid: Pid,
+    pub taken_at: String,
+    pub total_descriptors: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffGroup {
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    /// `None` for sockets and pipes, which are grouped by type only
+    pub path: Option<String>,
+    pub count: u32,
+}
+
+pub fn snapshot(args: &SnapshotArgs) -> ExitCode {
+    let descriptors = match validate_pid(args.pid).and_then(|pid| System.descriptors(pid)) {
+        Ok(descriptors) => descriptors,
+        Err(err) => {
+            let failure = crate::failure(args.pid, &err);
+            return crate::exit(OutputFormat::Json.render_failure(&failure), err.exit_code());
+        }
+    };
+    let snapshot = Snapshot {
+        snapshot_version: SNAPSHOT_VERSION,
+        pid: args.pid,
+        taken_at: format_timestamp(unix_timestamp(SystemTime::now())),
+        descriptors,
+    };
+
+    let mut bytes = serde_json::to_vec_pretty(&snapshot)
+        .unwrap_or_else(|err| panic!("could not serialize {:?}: {}", snapshot, err));
+    bytes.push(b'\n');
+    let written = match &args.output {
+        Some(path) => fs::write(path, &bytes),
+        None => io::stdout().write_all(&bytes),
+    };
+    match written {
+        Ok(()) => ExitCode::Ok,
+        Err(err) => {
+            let failure = Failure::io("Failed to write the snapshot".to_string(), &err);
+            crate::exit(OutputFormat::Json.render_failure(&failure), err.exit_code())
+        }
+    }
+}
+
+pub fn diff(args: &DiffArgs) -> ExitCode {
+    let format = match args.format.unwrap_or_else(OutputFormat::detect) {
+        format @ (OutputFormat::Json | OutputFormat::Table) => format,
+        _ => CliArgs::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "diff only supports --format json and --format table",
+            )
+            .exit(),
+    };
+
+    let before = match read(&args.before) {
+        Ok(snapshot) => snapshot,
+        Err(err) => return unreadable(&args.before, &err, format),
+    };
+    let after = match read(&args.after) {
+        Ok(snapshot) => snapshot,
+        Err(err) => return unreadable(&args.after, &err, format),
+    };
+
+    let diff = SnapshotDiff {
+        before: info(&before),
+        after: info(&after),
+        opened: changes(&after, &before),
+        closed: changes(&before, &after),
+    };
+    crate::exit(format.render_diff(&diff), ExitCode::Ok)
+}
+
+/// Fails for snapshots of another version, whose descriptors may not be comparable.
+fn read(path: &Path) -> io::Result<Snapshot> {
+    let contents = fs::read(path)?;
+    let snapshot: Snapshot = serde_json::from_slice(&contents)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    if snapshot.snapshot_version != SNAPSHOT_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "unsupported snapshot version {}, expected {}",
+                snapshot.snapshot_version, SNAPSHOT_VERSION
+            ),
+        ));
+    }
+    Ok(snapshot)
+}
+
+fn unreadable(path: &Path, err: &io::Error, format: OutputFormat) -> ExitCode {
+    let failure = Failure::io(format!("Failed to read snapshot {}", path.display()), err);
+    crate::exit(format.render_failure(&failure), err.exit_code())
+}
+
+fn info(snapshot: &Snapshot) -> SnapshotInfo {
+    SnapshotInfo {
+        pid: snapshot.pid,
+        taken_at: snapshot.taken_at.clone(),
+        total_descriptors: snapshot.descriptors.len() as u32,
+    }
+}
+
+/// Descriptors of `snapshot` that are not in `other`. A descriptor number
+/// that was reused for something else counts as closed and opened again.
+fn changes(snapshot: &Snapshot, other: &Snapshot) -> Vec<DiffGroup> {
+    let known: HashSet<&Descriptor> = other.descriptors.iter().collect();
+
+    let mut groups: BTreeMap<(&'static str, Option<&str>), u32> = BTreeMap::new();
+    for descriptor in &snapshot.descriptors {
+        if !known.contains(descriptor) {
+            let target = &descriptor.target;
+            *groups.entry((target.kind(), target.path())).or_default() += 1;
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|((kind, path), count)| DiffGroup {
+            kind,
+            path: path.map(str::to_string),
+            count,
+        })
+        .collect()
+}
@@ -0,0 +1,195 @@
+mod fake;
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fds::FdList;
+use crate::outcome::*;
+
+pub use fake::FakeSource;
+
+/// An open descriptor of a process and what it refers to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Descriptor {
+    pub fd: i32,
+    pub target: DescriptorTarget,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DescriptorTarget {
+    /// A regular file, directory or device, by path
+    File(String),
+    /// A socket, by inode
+    Socket(u64),
+    /// A pipe or FIFO, by inode
+    Pipe(u64),
+    /// An anonymous inode such as `[eventpoll]` or `inotify`
+    AnonInode(String),
+    Other,
+}
+
+impl DescriptorTarget {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DescriptorTarget::File(_) => "file",
+            DescriptorTarget::Socket(_) => "socket",
+            DescriptorTarget::Pipe(_) => "pipe",
+            DescriptorTarget::AnonInode(_) => "anon_inode",
+            DescriptorTarget::Other => "other",
+        }
+    }
+
+    /// The path of a file or the name of an anonymous inode. Sockets and
+    /// pipes only have an inode number.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            DescriptorTarget::File(path) | DescriptorTarget::AnonInode(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// Where descriptor information comes from. [`System`] inspects live
+/// processes, [`FakeSource`] serves them from a fixture.
+///
+/// Only [`DescriptorSource::descriptors`] is required. The counts have
+/// default implementations derived from it. The detailed reports, e.g. of Unix
+/// domain sockets or file locks, are built from the raw inputs a source
+/// provides, in the format of the corresponding Linux procfs files. A source
+/// that does not provide an input does not support the reports built from it.
+pub trait DescriptorSource {
+    /// Every descriptor the process has open.
+    fn descriptors(&self, pid: Pid) -> Result<Vec<Descriptor>, FshcError>;
+
+    /// Counts descriptors by what they refer to. Descriptors that are neither
+    /// files nor sockets, e.g. pipes, only contribute to the total.
+    fn list_by_type(&self, pid: Pid) -> Result<ProcStats, FshcError> {
+        let mut stats = ProcStats::new(pid);
+
+        let mut fd_n = 0;
+        let mut sd_n = 0;
+
+        for descriptor in self.descriptors(pid)? {
+            stats.total_descriptors += 1;
+            match descriptor.target {
+                DescriptorTarget::File(_) => fd_n += 1,
+                DescriptorTarget::Socket(_) => sd_n += 1,
+                _ => (),
+            }
+        }
+
+        stats.file_descriptors = Some(fd_n);
+        stats.socket_descriptors = Some(sd_n);
+
+        Ok(stats)
+    }
+
+    fn list_total(&self, pid: Pid) -> Result<ProcStats, FshcError> {
+        let mut stats = ProcStats::new(pid);
+        stats.total_descriptors = self.descriptors(pid)?.len() as u32;
+        Ok(stats)
+    }
+
+    fn limit(&self, _pid: Pid) -> Result<Option<DescriptorLimit>, FshcError> {
+        Ok(None)
+    }
+
+    /// The contents of `/proc/<pid>/fdinfo/<fd>`, or `None` if the
+    /// descriptor has been closed since it was listed.
+    fn fdinfo(&self, _pid: Pid, _fd: i32) -> Result<Option<String>, FshcError> {
+        Err(FshcError::Unsupported)
+    }
+
+    /// The inode of the file a descriptor refers to, or `None` if it cannot
+    /// be determined.
+    fn inode(&self, _pid: Pid, _fd: i32) -> Result<Option<u64>, FshcError> {
+        Err(FshcError::Unsupported)
+    }
+
+    /// The rows of `/proc/<pid>/net/unix`, without the header.
+    fn unix_socket_table(&self, _pid: Pid) -> Result<Vec<String>, FshcError> {
+        Err(FshcError::Unsupported)
+    }
+
+    /// The inode of the peer of every connected Unix domain socket, if known.
+    fn unix_socket_peers(&self, _pid: Pid) -> Option<HashMap<u64, u64>> {
+        None
+    }
+
+    /// The lines of `/proc/<pid>/maps`.
+    fn maps(&self, _pid: Pid) -> Result<Vec<String>, FshcError> {
+        Err(FshcError::Unsupported)
+    }
+
+    /// The `fs.inotify.max_user_watches` limit, if known.
+    fn max_user_inotify_watches(&self) -> Option<u64> {
+        None
+    }
+
+    /// The number of `/proc/<pid>/map_files` entries, if they can be listed.
+    fn map_files_entries(&self, _pid: Pid) -> Option<u32> {
+        None
+    }
+}
+
+/// The processes of the running system: procfs on Linux, libproc on macOS
+/// and the handle table on Windows.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct System;
+
+impl DescriptorSource for System {
+    fn descriptors(&self, pid: Pid) -> Result<Vec<Descriptor>, FshcError> {
+        FdList::descriptors(pid)
+    }
+
+    // macOS and Windows can count descriptors by type without listing them
+    #[cfg(not(target_os = "linux"))]
+    fn list_by_type(&self, pid: Pid) -> Result<ProcStats, FshcError> {
+        FdList::list_by_type(pid)
+    }
+
+    fn list_total(&self, pid: Pid) -> Result<ProcStats, FshcError> {
+        FdList::list_total(pid)
+    }
+
+    fn limit(&self, pid: Pid) -> Result<Option<DescriptorLimit>, FshcError> {
+        FdList::limit(pid)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn fdinfo(&self, pid: Pid, fd: i32) -> Result<Option<String>, FshcError> {
+        FdList::fdinfo(pid, fd)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn inode(&self, pid: Pid, fd: i32) -> Result<Option<u64>, FshcError> {
+        Ok(FdList::inode(pid, fd))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn unix_socket_table(&self, pid: Pid) -> Result<Vec<String>, FshcError> {
+        FdList::unix_socket_table(pid)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn unix_socket_peers(&self, pid: Pid) -> Option<HashMap<u64, u64>> {
+        FdList::unix_socket_peers(pid)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn maps(&self, pid: Pid) -> Result<Vec<String>, FshcError> {
+        FdList::maps(pid)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn max_user_inotify_watches(&self) -> Option<u64> {
+        FdList::max_user_inotify_watches()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn map_files_entries(&self, pid: Pid) -> Option<u32> {
+        FdList::map_files_entries(pid)
+    }
+}
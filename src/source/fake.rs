@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// A descriptor source that serves processes from a fixture description
+/// instead of inspecting the system, e.g.
+///
+/// ```json
+/// {
+///   "processes": {
+///     "42": {
+///       "descriptors": [
+///         { "fd": 0, "target": { "file": "/dev/null" } },
+///         { "fd": 3, "target": { "socket": 81234 } },
+///         { "fd": 4, "target": { "pipe": 81235 } },
+///         { "fd": 5, "target": "other" }
+///       ],
+///       "limit": { "soft": 1024, "hard": 4096 },
+///       "fdinfo": { "0": "pos:\t0\nflags:\t0100000\n" },
+///       "inodes": { "0": 4 },
+///       "unix_sockets": ["0000000000000000: 00000002 00000000 00010000 0001 01 81234 /run/app.sock"],
+///       "unix_socket_peers": { "81234": 81240 },
+///       "maps": ["7f0000000000-7f0000001000 r--s 00000000 00:06 4 /dev/null"],
+///       "map_files_entries": 1
+///     }
+///   },
+///   "max_user_inotify_watches": 8192
+/// }
+/// ```
+///
+/// The raw inputs use the format of the corresponding Linux procfs files:
+/// `fdinfo` is keyed by descriptor, `unix_sockets` holds the rows of
+/// `/proc/<pid>/net/unix` without the header and `maps` the lines of
+/// `/proc/<pid>/maps`. `unix_socket_peers` maps socket inodes to the inodes
+/// of their peers, like sock_diag(7) does. Reports that
+/// need an input a process does not describe are unsupported.
+///
+/// Processes that are not described do not exist.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct FakeSource {
+    processes: HashMap<Pid, FakeProcess>,
+    max_user_inotify_watches: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FakeProcess {
+    descriptors: Vec<Descriptor>,
+    limit: Option<DescriptorLimit>,
+    fdinfo: Option<HashMap<i32, String>>,
+    inodes: HashMap<i32, u64>,
+    unix_sockets: Option<Vec<String>>,
+    unix_socket_peers: Option<HashMap<u64, u64>>,
+    maps: Option<Vec<String>>,
+    map_files_entries: Option<u32>,
+}
+
+impl FakeSource {
+    pub fn from_json(fixture: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(fixture)
+    }
+
+    fn process(&self, pid: Pid) -> Result<&FakeProcess, FshcError> {
+        self.processes
+            .get(&pid)
+            .ok_or(FshcError::NotFound { errno: None })
+    }
+}
+
+impl DescriptorSource for FakeSource {
+    fn descriptors(&self, pid: Pid) -> Result<Vec<Descriptor>, FshcError> {
+        Ok(self.process(pid)?.descriptors.clone())
+    }
+
+    fn limit(&self, pid: Pid) -> Result<Option<DescriptorLimit>, FshcError> {
+        Ok(self.process(pid)?.limit)
+    }
+
+    fn fdinfo(&self, pid: Pid, fd: i32) -> Result<Option<String>, FshcError> {
+        let fdinfo = self.process(pid)?.fdinfo.as_ref();
+        Ok(fdinfo.ok_or(FshcError::Unsupported)?.get(&fd).cloned())
+    }
+
+    fn inode(&self, pid: Pid, fd: i32) -> Result<Option<u64>, FshcError> {
+        Ok(self.process(pid)?.inodes.get(&fd).copied())
+    }
+
+    fn unix_socket_table(&self, pid: Pid) -> Result<Vec<String>, FshcError> {
+        supported(&self.process(pid)?.unix_sockets)
+    }
+
+    fn unix_socket_peers(&self, pid: Pid) -> Option<HashMap<u64, u64>> {
+        self.process(pid).ok()?.unix_socket_peers.clone()
+    }
+
+    fn maps(&self, pid: Pid) -> Result<Vec<String>, FshcError> {
+        supported(&self.process(pid)?.maps)
+    }
+
+    fn max_user_inotify_watches(&self) -> Option<u64> {
+        self.max_user_inotify_watches
+    }
+
+    fn map_files_entries(&self, pid: Pid) -> Option<u32> {
+        self.process(pid).ok()?.map_files_entries
+    }
+}
+
+fn supported(rows: &Option<Vec<String>>) -> Result<Vec<String>, FshcError> {
+    rows.clone().ok_or(FshcError::Unsupported)
+}
@@ -0,0 +1,42 @@
+//! An async API for querying many processes concurrently.
+//!
+//! Descriptors are read with blocking system calls, so every query runs on
+//! a thread pool. The API does not depend on a particular async runtime.
+
+use futures_util::stream::{self, Stream, StreamExt};
+
+use crate::{FshcResult, Pid, QueryOptions, count_descriptors};
+
+/// Queries the given processes with at most `concurrency` queries in flight
+/// and yields every result together with its pid, in order of completion.
+///
+/// ```no_run
+/// use futures_util::StreamExt;
+///
+/// # async fn example() {
+/// let results = fshc::stream::count_descriptors_many(
+///     [1, 73847],
+///     fshc::QueryOptions::default(),
+///     16,
+/// );
+/// futures_util::pin_mut!(results);
+/// while let Some((pid, result)) = results.next().await {
+///     println!("{pid}: {:?}", result.map(|stats| stats.total_descriptors));
+/// }
+/// # }
+/// ```
+pub fn count_descriptors_many<I>(
+    pids: I,
+    options: QueryOptions,
+    concurrency: usize,
+) -> impl Stream<Item = (Pid, FshcResult)>
+where
+    I: IntoIterator<Item = Pid>,
+{
+    stream::iter(pids)
+        .map(move |pid| {
+            let options = options.clone();
+            blocking::unblock(move || (pid, count_descriptors(pid, &options)))
+        })
+        .buffer_unordered(concurrency.max(1))
+}
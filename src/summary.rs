@@ -0,0 +1,122 @@
+use fshc::outcome::{Pid, ProcStats};
+use serde::Serialize;
+
+use crate::time::format_timestamp;
+
+/// The categories of descriptors that are summarized, in the order they are reported.
+const CATEGORIES: [&str; 3] = [
+    "total_descriptors",
+    "socket_descriptors",
+    "file_descriptors",
+];
+
+/// How the descriptors of a process, or of a recording, behaved over a series of samples.
+#[derive(Debug, Serialize)]
+pub struct HistorySummary {
+    /// Omitted for recordings, which can span restarts of a process
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<Pid>,
+    pub samples: usize,
+    pub first_sampled_at: Option<String>,
+    pub last_sampled_at: Option<String>,
+    pub categories: Vec<Summary>,
+}
+
+/// How one category of descriptors, e.g. sockets, behaved over a series of samples.
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    pub category: &'static str,
+    pub min: u64,
+    pub max: u64,
+    pub mean: f64,
+    pub p50: u64,
+    pub p95: u64,
+    pub p99: u64,
+    /// When `max` was first reached, as an RFC 3339 timestamp
+    pub peak_at: String,
+}
+
+/// Descriptor counts in the order they were sampled.
+#[derive(Debug, Default)]
+pub struct History {
+    /// Seconds since the Unix epoch and the counts of [`CATEGORIES`]
+    samples: Vec<(u64, [Option<u64>; 3])>,
+}
+
+impl History {
+    pub fn push(
+        &mut self,
+        sampled_at: u64,
+        total_descriptors: u32,
+        socket_descriptors: Option<u32>,
+        file_descriptors: Option<u32>,
+    ) {
+        self.samples.push((
+            sampled_at,
+            [
+                Some(total_descriptors.into()),
+                socket_descriptors.map(u64::from),
+                file_descriptors.map(u64::from),
+            ],
+        ));
+    }
+
+    pub fn push_stats(&mut self, sampled_at: u64, stats: &ProcStats) {
+        self.push(
+            sampled_at,
+            stats.total_descriptors,
+            stats.socket_descriptors,
+            stats.file_descriptors,
+        );
+    }
+
+    /// Summarizes every category that was sampled at least once.
+    pub fn summarize(&self, pid: Option<Pid>) -> HistorySummary {
+        let categories = CATEGORIES
+            .iter()
+            .enumerate()
+            .filter_map(|(i, category)| {
+                let values = self
+                    .samples
+                    .iter()
+                    .filter_map(|(sampled_at, counts)| counts[i].map(|n| (n, *sampled_at)));
+                summarize(category, values.collect())
+            })
+            .collect();
+
+        HistorySummary {
+            pid,
+            samples: self.samples.len(),
+            first_sampled_at: self.samples.first().map(|(t, _)| format_timestamp(*t)),
+            last_sampled_at: self.samples.last().map(|(t, _)| format_timestamp(*t)),
+            categories,
+        }
+    }
+}
+
+/// Summarizes `(value, sampled_at)` pairs in the order they were sampled.
+/// Returns `None` if there are no samples.
+fn summarize(category: &'static str, mut values: Vec<(u64, u64)>) -> Option<Summary> {
+    let (_, peak_at) = values
+        .iter()
+        .rev()
+        .max_by_key(|(value, _)| *value)
+        .copied()?;
+    let sum: u128 = values.iter().map(|(value, _)| u128::from(*value)).sum();
+    let mean = sum as f64 / values.len() as f64;
+
+    values.sort_unstable_by_key(|(value, _)| *value);
+    // The nearest-rank method: the smallest value that at least p% of the samples do not exceed
+    let percentile = |p: usize| values[(values.len() * p).div_ceil(100).max(1) - 1].0;
+
+    Some(Summary {
+        category,
+        min: values[0].0,
+        max: values[values.len() - 1].0,
+        mean,
+        p50: percentile(50),
+        p95: percentile(95),
+        p99: percentile(99),
+        peak_at: format_timestamp(peak_at),
+    })
+}
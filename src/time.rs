@@ -0,0 +1,72 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Parses durations such as `500ms`, `10s`, `5m` or `1h`.
+/// A number without a unit is interpreted as seconds.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split_at = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split_at);
+
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("'{value}' is not a valid duration, expected e.g. 10s or 5m"))?;
+    let duration = match unit {
+        "ms" => Some(Duration::from_millis(number)),
+        "" | "s" => Some(Duration::from_secs(number)),
+        "m" => number.checked_mul(60).map(Duration::from_secs),
+        "h" => number.checked_mul(60 * 60).map(Duration::from_secs),
+        other => {
+            return Err(format!(
+                "unknown duration unit '{other}', use ms, s, m or h"
+            ));
+        }
+    };
+
+    duration.ok_or_else(|| format!("'{value}' is too long a duration"))
+}
+
+/// Like [`parse_duration`], but rejects 0, which would poll without pausing.
+pub fn parse_interval(value: &str) -> Result<Duration, String> {
+    match parse_duration(value)? {
+        Duration::ZERO => Err("the interval must be longer than 0".to_string()),
+        duration => Ok(duration),
+    }
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Formats a Unix timestamp as an RFC 3339 date and time in UTC,
+/// e.g. `2026-02-02T14:03:27Z`.
+pub fn format_timestamp(secs: u64) -> String {
+    let days = (secs / 86_400) as i64;
+    let time_of_day = secs % 86_400;
+
+    // Converts days since the epoch to a civil date, see
+    // <https://howardhinnant.github.io/date_algorithms.html#civil_from_days>
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time_of_day / 3_600,
+        time_of_day % 3_600 / 60,
+        time_of_day % 60
+    )
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+use std::thread;
+use std::time::Instant;
+
+use fshc::outcome::*;
+use fshc::{QueryOptions, count_descriptors};
+use sysexits::ExitCode;
+
+use crate::cli::WaitArgs;
+use crate::format::OutputFormat;
+
+/// A condition on the descriptor counts of a process, e.g. `sockets<100`.
+#[derive(Debug, Clone, Copy)]
+pub struct Condition {
+    category: Category,
+    operator: Operator,
+    value: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Category {
+    Total,
+    Sockets,
+    Files,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Operator {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+/// Parses conditions such as `sockets<100`, `files >= 10` or `total==0`.
+pub fn parse_condition(value: &str) -> Result<Condition, String> {
+    let invalid = || format!("'{value}' is not a valid condition, expected e.g. sockets<100");
+
+    let split_at = value.find(['<', '>', '=', '!']).ok_or_else(invalid)?;
+    let (category, rest) = value.split_at(split_at);
+    let category = match category.trim() {
+        "total" => Category::Total,
+        "sockets" => Category::Sockets,
+        "files" => Category::Files,
+        other => {
+            return Err(format!(
+                "unknown category '{other}', use total, sockets or files"
+            ));
+        }
+    };
+
+    // Two-character operators have to be tried first
+    let operators = [
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        ("==", Operator::Equal),
+        ("!=", Operator::NotEqual),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+    ];
+    let (operator, value) = operators
+        .iter()
+        .find_map(|(symbol, operator)| rest.strip_prefix(symbol).map(|v| (*operator, v)))
+        .ok_or_else(invalid)?;
+    let value = value.trim().parse().map_err(|_| invalid())?;
+
+    Ok(Condition {
+        category,
+        operator,
+        value,
+    })
+}
+
+impl Condition {
+    /// Whether the condition holds for `stats`. Fails if the count it is
+    /// about was not collected, e.g. because the platform does not provide it.
+    fn holds(&self, stats: &ProcStats) -> Result<bool, FshcError> {
+        let count = self.count(stats).ok_or(FshcError::Unsupported)?;
+        Ok(match self.operator {
+            Operator::Less => count < self.value,
+            Operator::LessOrEqual => count <= self.value,
+            Operator::Greater => count > self.value,
+            Operator::GreaterOrEqual => count >= self.value,
+            Operator::Equal => count == self.value,
+            Operator::NotEqual => count != self.value,
+        })
+    }
+
+    fn count(&self, stats: &ProcStats) -> Option<u64> {
+        match self.category {
+            Category::Total => Some(stats.total_descriptors.into()),
+            Category::Sockets => stats.socket_descriptors.map(u64::from),
+            Category::Files => stats.file_descriptors.map(u64::from),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let category = match self.category {
+            Category::Total => "total",
+            Category::Sockets => "sockets",
+            Category::Files => "files",
+        };
+        let operator = match self.operator {
+            Operator::Less => "<",
+            Operator::LessOrEqual => "<=",
+            Operator::Greater => ">",
+            Operator::GreaterOrEqual => ">=",
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+        };
+        write!(f, "{}{}{}", category, operator, self.value)
+    }
+}
+
+/// Polls the process until all conditions hold, then prints its stats.
+/// Fails with a `timed_out` failure if they do not hold before the timeout.
+pub fn run(args: &WaitArgs) -> ExitCode {
+    let format = args.format.unwrap_or_else(OutputFormat::detect);
+    let deadline = args.timeout.map(|timeout| Instant::now() + timeout);
+    // Counting is cheaper when sockets and files do not have to be told apart
+    let options = QueryOptions {
+        only_total: args.until.iter().all(|c| c.category == Category::Total),
+        ..Default::default()
+    };
+
+    loop {
+        let unmet = match check(args.pid, &options, &args.until) {
+            Ok((stats, unmet)) if unmet.is_empty() => {
+                return crate::exit(format.render_stats(&stats), ExitCode::Ok);
+            }
+            Ok((_, unmet)) => unmet,
+            Err(err) => {
+                let failure = crate::failure(args.pid, &err);
+                return crate::exit(format.render_failure(&failure), err.exit_code());
+            }
+        };
+
+        let now = Instant::now();
+        match deadline {
+            Some(deadline) if now >= deadline => {
+                let conditions: Vec<String> = args.until.iter().map(|c| c.to_string()).collect();
+                let failure = Failure {
+                    details: format!("the last sample had {}", unmet.join(" and ")),
+                    ..Failure::new(
+                        Some(args.pid),
+                        format!(
+                            "Process {} did not reach {} in time",
+                            args.pid,
+                            conditions.join(" and ")
+                        ),
+                        &FshcError::TimedOut,
+                    )
+                };
+                return crate::exit(format.render_failure(&failure), ExitCode::TempFail);
+            }
+            Some(deadline) => thread::sleep(args.interval.min(deadline - now)),
+            None => thread::sleep(args.interval),
+        }
+    }
+}
+
+/// Samples the process once. Returns its stats and the counts of the conditions that do not hold.
+fn check(
+    pid: Pid,
+    options: &QueryOptions,
+    conditions: &[Condition],
+) -> Result<(ProcStats, Vec<String>), FshcError> {
+    let stats = count_descriptors(pid, options)?;
+    let mut unmet = Vec::new();
+    for condition in conditions {
+        if !condition.holds(&stats)? {
+            unmet.push(describe(condition, &stats));
+        }
+    }
+    Ok((stats, unmet))
+}
+
+/// The count a condition is about, e.g. `130 sockets`.
+fn describe(condition: &Condition, stats: &ProcStats) -> String {
+    let count = condition.count(stats).unwrap_or_default();
+    match condition.category {
+        Category::Total => format!("{} descriptors", count),
+        Category::Sockets => format!("{} sockets", count),
+        Category::Files => format!("{} files", count),
+    }
+}
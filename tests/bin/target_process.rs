@@ -13,7 +13,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-//! Test helper binary that opens 1 file and 2 sockets.
+//! Test helper binary that opens (and locks) 1 file and 2 sockets,
+//! plus a Unix domain socket listener on Unix. On Linux, it also registers
+//! both sockets with an epoll instance and watches the file with inotify.
 //! Outputs PID on stdout, waits for stdin input, then exits.
 
 use std::fs::File;
@@ -21,9 +23,15 @@ use std::io::{self, BufRead};
 use std::net::TcpListener;
 
 fn main() {
-    let _file = File::create(temp_file_path()).expect("Failed to create temp file");
+    let file = File::create(temp_file_path()).expect("Failed to create temp file");
+    file.lock().expect("Failed to lock temp file");
     let _socket1 = TcpListener::bind("127.0.0.1:0").expect("Failed to bind socket 1");
     let _socket2 = TcpListener::bind("127.0.0.1:0").expect("Failed to bind socket 2");
+    #[cfg(unix)]
+    let _unix_socket = std::os::unix::net::UnixListener::bind(unix_socket_path())
+        .expect("Failed to bind Unix socket");
+    #[cfg(target_os = "linux")]
+    let _watches = watch(&[&_socket1, &_socket2]);
 
     println!("{}", std::process::id());
 
@@ -31,6 +39,31 @@ fn main() {
     let _ = stdin.lock().lines().next();
 
     let _ = std::fs::remove_file(temp_file_path());
+    #[cfg(unix)]
+    let _ = std::fs::remove_file(unix_socket_path());
+}
+
+#[cfg(target_os = "linux")]
+fn watch(sockets: &[&TcpListener]) -> [std::os::fd::OwnedFd; 2] {
+    use rustix::event::epoll;
+    use rustix::fs::inotify;
+
+    let epoll = epoll::create(epoll::CreateFlags::CLOEXEC).expect("Failed to create epoll");
+    for (i, socket) in sockets.iter().enumerate() {
+        epoll::add(
+            &epoll,
+            socket,
+            epoll::EventData::new_u64(i as u64),
+            epoll::EventFlags::IN,
+        )
+        .expect("Failed to register socket with epoll");
+    }
+
+    let inotify = inotify::init(inotify::CreateFlags::CLOEXEC).expect("Failed to create inotify");
+    inotify::add_watch(&inotify, temp_file_path(), inotify::WatchFlags::MODIFY)
+        .expect("Failed to watch temp file");
+
+    [epoll, inotify]
 }
 
 fn temp_file_path() -> String {
@@ -40,3 +73,12 @@ fn temp_file_path() -> String {
         std::process::id()
     )
 }
+
+#[cfg(unix)]
+fn unix_socket_path() -> String {
+    format!(
+        "{}/fshc_test_{}.sock",
+        std::env::temp_dir().display(),
+        std::process::id()
+    )
+}
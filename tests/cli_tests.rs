@@ -15,10 +15,14 @@
 
 mod test_helpers;
 
+use assert_cmd::cargo::cargo_bin_cmd;
+use predicates::prelude::*;
 use std::error::Error;
-use std::io::{BufRead, BufReader, Write};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, UdpSocket};
 use std::process::{Command, Stdio};
-use test_helpers::{output_includes, run_fails, run_succeeds, target_process_bin};
+use std::time::Duration;
+use test_helpers::{fshc_bin, output_includes, run_fails, run_succeeds, target_process_bin};
 
 #[test]
 fn show_help_with_help_flag() -> Result<(), Box<dyn Error>> {
@@ -57,6 +61,15 @@ fn fail_with_invalid_pid_too_large() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[test]
+fn fail_with_stable_error_codes() -> Result<(), Box<dyn Error>> {
+    let assert = run_fails(["--pid", "0", "--format", "json"]);
+    let failure: serde_json::Value = serde_json::from_slice(&assert.get_output().stderr)?;
+    assert_eq!(failure["code"], "pid_out_of_range");
+    assert_eq!(failure["exit_code"], 65);
+    Ok(())
+}
+
 #[test]
 fn query_target_process_returns_json_with_descriptors() -> Result<(), Box<dyn Error>> {
     let mut child = Command::new(target_process_bin())
@@ -71,6 +84,7 @@ fn query_target_process_returns_json_with_descriptors() -> Result<(), Box<dyn Er
     let target_pid = pid_line.trim();
 
     let _ = run_succeeds(["--pid", target_pid])
+        .stdout(output_includes("\"schema_version\":1"))
         .stdout(output_includes(&format!("\"pid\":{}", target_pid)))
         .stdout(output_includes("\"total_descriptors\":"))
         .stdout(output_includes("\"file_descriptors\":"));
@@ -146,3 +160,846 @@ fn query_target_process_has_expected_minimum_descriptors() -> Result<(), Box<dyn
 
     Ok(())
 }
+
+#[test]
+fn query_target_process_with_prometheus_format() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let _ = run_succeeds(["--pid", target_pid, "--format", "prometheus"])
+        .stdout(output_includes("# HELP fshc_descriptors "))
+        .stdout(output_includes("# TYPE fshc_descriptors gauge"))
+        .stdout(output_includes(&format!(
+            "fshc_open_descriptors{{pid=\"{}\"}} ",
+            target_pid
+        )))
+        .stdout(output_includes(&format!(
+            "fshc_descriptors{{pid=\"{}\",type=\"file\"}} ",
+            target_pid
+        )))
+        // totals are not part of the per-type family, which would count them twice
+        .stdout(output_includes("type=\"total\"").not());
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn fail_with_prometheus_format_reports_json_error() -> Result<(), Box<dyn Error>> {
+    run_fails(["--pid", "0", "--format", "prometheus"]).stderr(output_includes(
+        "\"details\":\"only pid numbers between 1 and 99999",
+    ));
+    Ok(())
+}
+
+#[test]
+fn print_nothing_to_stdout_when_every_process_fails() -> Result<(), Box<dyn Error>> {
+    for pids in ["0", "0,100000"] {
+        for format in ["prometheus", "csv", "tsv", "influx"] {
+            run_fails(["--pid", pids, "--format", format])
+                .stdout("")
+                .stderr(output_includes("only pid numbers between 1 and 99999"));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn serve_target_process_metrics_over_http() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let mut server = Command::new(fshc_bin())
+        .args(["serve", "--pid", target_pid, "--listen", "127.0.0.1:0"])
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stderr = server.stderr.take().expect("Failed to get stderr");
+    let mut address_line = String::new();
+    BufReader::new(stderr).read_line(&mut address_line)?;
+    let address = address_line
+        .trim()
+        .trim_start_matches("Serving metrics on http://")
+        .trim_end_matches("/metrics")
+        .to_string();
+
+    let mut stream = TcpStream::connect(&address)?;
+    stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+
+    let _ = server.kill();
+    let _ = server.wait();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+    assert!(response.contains(&format!("fshc_open_descriptors{{pid=\"{}\"}} ", target_pid)));
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn query_target_process_with_table_format() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--format", "table"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let mut lines = output.lines();
+
+    let header: Vec<&str> = lines
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    assert_eq!(
+        header,
+        ["PID", "TOTAL", "SOCKETS", "FILES", "LIMIT", "UTILIZATION"]
+    );
+    let row: Vec<&str> = lines
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    assert_eq!(row.first(), Some(&target_pid));
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn query_several_processes_reports_processes_and_failures() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_fails(["--pid", target_pid, "--pid", "0"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: serde_json::Value = serde_json::from_str(&output)?;
+
+    let processes = json["processes"].as_array().expect("Expected processes");
+    assert_eq!(processes.len(), 1);
+    assert_eq!(processes[0]["pid"].to_string(), target_pid);
+    let failures = json["failures"].as_array().expect("Expected failures");
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0]["pid"], 0);
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn query_several_processes_on_worker_threads() -> Result<(), Box<dyn Error>> {
+    let own_pid = std::process::id().to_string();
+    let pids = [own_pid.as_str(), "0", own_pid.as_str()].join(",");
+
+    let assert = run_fails([
+        "--pid",
+        &pids,
+        "--jobs",
+        "2",
+        "--timeout",
+        "10s",
+        "--format",
+        "json",
+    ])
+    .code(65);
+    let report: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+
+    assert_eq!(report["processes"].as_array().map(Vec::len), Some(2));
+    assert_eq!(report["failures"][0]["code"], "pid_out_of_range");
+    Ok(())
+}
+
+#[test]
+fn fail_with_zero_jobs() -> Result<(), Box<dyn Error>> {
+    run_fails(["--pid", "1", "--jobs", "0"]).stderr(output_includes("--jobs"));
+    Ok(())
+}
+
+#[test]
+fn sample_target_process_with_csv_format() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds([
+        "--pid",
+        target_pid,
+        "--format",
+        "csv",
+        "--interval",
+        "10ms",
+        "--count",
+        "3",
+    ]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let lines: Vec<&str> = output.lines().collect();
+
+    // one header row and one row per sample
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("timestamp,pid,total_descriptors,"));
+    let columns = lines[0].split(',').count();
+    for row in &lines[1..] {
+        let values: Vec<&str> = row.split(',').collect();
+        assert_eq!(values.len(), columns);
+        assert_eq!(values[1], target_pid);
+    }
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn sample_target_process_with_tsv_format() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let _ = run_succeeds(["--pid", target_pid, "--format", "tsv"])
+        .stdout(output_includes("timestamp\tpid\ttotal_descriptors\t"))
+        .stdout(output_includes(&format!("Z\t{}\t", target_pid)));
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn fail_with_count_but_no_interval() -> Result<(), Box<dyn Error>> {
+    run_fails(["--pid", "1", "--count", "3"]).stderr(output_includes("--interval"));
+    Ok(())
+}
+
+#[test]
+fn query_target_process_with_influx_format() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--format", "influx"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let parts: Vec<&str> = output.trim().split(' ').collect();
+
+    assert_eq!(parts.len(), 3, "Expected measurement, fields and timestamp");
+    assert_eq!(parts[0], format!("fshc,pid={}", target_pid));
+    assert!(parts[1].starts_with("total_descriptors="));
+    assert!(parts[1].split(',').all(|field| field.ends_with('i')));
+    assert!(parts[2].parse::<u128>().is_ok());
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn send_target_process_stats_to_statsd() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let agent = UdpSocket::bind("127.0.0.1:0")?;
+    agent.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let agent_addr = agent.local_addr()?.to_string();
+
+    let assert = run_succeeds(["--pid", target_pid, "--statsd-addr", &agent_addr]);
+    assert!(assert.get_output().stdout.is_empty());
+
+    let mut buf = [0u8; 512];
+    let n = agent.recv(&mut buf)?;
+    let datagram = String::from_utf8_lossy(&buf[..n]);
+    assert!(datagram.starts_with(&format!("fshc.{}.total_descriptors:", target_pid)));
+    assert!(datagram.ends_with("|g"));
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn fail_with_statsd_addr_and_another_format() -> Result<(), Box<dyn Error>> {
+    run_fails([
+        "--pid",
+        "1",
+        "--format",
+        "json",
+        "--statsd-addr",
+        "127.0.0.1:8125",
+    ])
+    .stderr(output_includes("--statsd-addr"));
+    Ok(())
+}
+
+#[test]
+fn query_target_process_with_etf_format() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--format", "etf"]);
+    let output = &assert.get_output().stdout;
+
+    // version 131 followed by a map
+    assert_eq!(output[..2], [131, 116]);
+    let mut pid_term = vec![119, 3, b'p', b'i', b'd'];
+    let pid: u32 = target_pid.parse()?;
+    if pid <= u8::MAX as u32 {
+        pid_term.extend([97, pid as u8]);
+    } else {
+        pid_term.push(98);
+        pid_term.extend(pid.to_be_bytes());
+    }
+    assert!(output.windows(pid_term.len()).any(|w| w == pid_term));
+    let total_key = [&[119, 17][..], b"total_descriptors"].concat();
+    assert!(output.windows(total_key.len()).any(|w| w == total_key));
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn fail_with_etf_format_reports_etf_error() -> Result<(), Box<dyn Error>> {
+    let assert = run_fails(["--pid", "0", "--format", "etf"]);
+    let output = &assert.get_output().stderr;
+
+    assert_eq!(output[..2], [131, 116]);
+    let details_key = [&[119, 7][..], b"details", &[109]].concat();
+    assert!(output.windows(details_key.len()).any(|w| w == details_key));
+    Ok(())
+}
+
+#[test]
+fn answer_framed_requests_in_port_mode() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid: u32 = pid_line.trim().parse()?;
+
+    let mut port = Command::new(fshc_bin())
+        .args(["port", "--format", "json"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut port_stdin = port.stdin.take().expect("Failed to get stdin");
+    let mut port_stdout = port.stdout.take().expect("Failed to get stdout");
+
+    let mut exchange = |request: &[u8]| -> Result<serde_json::Value, Box<dyn Error>> {
+        port_stdin.write_all(&(request.len() as u32).to_be_bytes())?;
+        port_stdin.write_all(request)?;
+        port_stdin.flush()?;
+        let mut len = [0u8; 4];
+        port_stdout.read_exact(&mut len)?;
+        let mut response = vec![0u8; u32::from_be_bytes(len) as usize];
+        port_stdout.read_exact(&mut response)?;
+        Ok(serde_json::from_slice(&response)?)
+    };
+
+    // term_to_binary(#{pid => Pid, only_total => true})
+    let mut request = vec![131, 116, 0, 0, 0, 2, 119, 3, b'p', b'i', b'd', 98];
+    request.extend(target_pid.to_be_bytes());
+    request.extend([&[119, 10][..], b"only_total", &[119, 4], b"true"].concat());
+    let stats = exchange(&request)?;
+    assert_eq!(stats["pid"], target_pid);
+    assert!(stats["total_descriptors"].as_u64().unwrap() > 0);
+    assert!(stats.get("socket_descriptors").is_none());
+
+    // the port stays alive between requests, including failed ones
+    let failure = exchange(br#"{"pid": 0}"#)?;
+    assert_eq!(failure["pid"], 0);
+    let failure = exchange(b"not a request")?;
+    assert_eq!(failure["message"], "Failed to decode request");
+    // an oversized frame is skipped rather than buffered, and the next one is read in sync
+    let failure = exchange(&vec![b' '; 64 * 1024 + 1])?;
+    assert_eq!(failure["code"], "invalid_request");
+    assert!(failure["details"].as_str().unwrap().contains("65537 bytes"));
+
+    let stats = exchange(format!(r#"{{"pid": {target_pid}}}"#).as_bytes())?;
+    assert!(stats["total_descriptors"].as_u64().unwrap() >= 2);
+    // Windows does not count sockets separately
+    if cfg!(unix) {
+        assert!(stats["socket_descriptors"].as_u64().unwrap() >= 2);
+    }
+
+    drop(port_stdin);
+    assert!(port.wait()?.success());
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn answer_json_lines_requests_in_batch_mode() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let mut input =
+        format!("{{\"pid\":{target_pid},\"only_total\":true}}\n\n{{\"pid\":0}}\nnot json\n")
+            .into_bytes();
+    // a line that is not UTF-8 is answered like any other bad request
+    input.extend(b"{\"pid\":\xff}\n");
+    input.extend(format!("{{\"pid\":{target_pid}}}\n").into_bytes());
+    let assert = cargo_bin_cmd!("fshc")
+        .arg("batch")
+        .write_stdin(input)
+        .assert()
+        .success();
+    let output = String::from_utf8(assert.get_output().stdout.clone())?;
+    let responses: Vec<serde_json::Value> = output
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+
+    assert_eq!(responses.len(), 5);
+    assert_eq!(responses[0]["pid"].to_string(), target_pid);
+    assert!(responses[0].get("socket_descriptors").is_none());
+    assert_eq!(responses[1]["pid"], 0);
+    assert!(responses[1]["details"].is_string());
+    assert_eq!(responses[2]["message"], "Failed to decode request");
+    assert_eq!(responses[3]["code"], "invalid_request");
+    assert!(responses[4]["total_descriptors"].as_u64().unwrap() >= 2);
+    // Windows does not count sockets separately
+    if cfg!(unix) {
+        assert!(responses[4]["socket_descriptors"].as_u64().unwrap() >= 2);
+    }
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn print_json_schema_of_output() -> Result<(), Box<dyn Error>> {
+    let assert = run_succeeds(["schema"]);
+    let schema: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+
+    for definition in ["ProcStats", "Failure", "Report"] {
+        assert!(schema["$defs"][definition]["properties"].is_object());
+    }
+    assert_eq!(schema["oneOf"].as_array().map(Vec::len), Some(3));
+    assert_eq!(
+        schema["oneOf"][0]["properties"]["schema_version"]["const"],
+        1
+    );
+    assert!(schema["$defs"]["ProcStats"]["properties"]["mappings"].is_object());
+    Ok(())
+}
+
+#[test]
+fn record_samples_and_replay_the_most_recent_ones() -> Result<(), Box<dyn Error>> {
+    let own_pid = std::process::id().to_string();
+    let path = std::env::temp_dir().join(format!("fshc_recording_{own_pid}.db"));
+    let _ = std::fs::remove_file(&path);
+    let file = path.to_str().expect("Expected a UTF-8 temporary path");
+    let replay = || -> Result<Vec<serde_json::Value>, Box<dyn Error>> {
+        let assert = run_succeeds(["replay", "--file", file, "--format", "json"]);
+        let samples = assert
+            .get_output()
+            .stdout
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(serde_json::from_slice)
+            .collect::<Result<_, _>>()?;
+        Ok(samples)
+    };
+    let pids = |samples: &[serde_json::Value]| -> Vec<String> {
+        samples.iter().map(|s| s["pid"].to_string()).collect()
+    };
+
+    run_succeeds([
+        "record",
+        "--pid",
+        &own_pid,
+        "--interval",
+        "10ms",
+        "--file",
+        file,
+        "--capacity",
+        "3",
+        "--count",
+        "5",
+    ]);
+    assert_eq!(pids(&replay()?), [own_pid.as_str(); 3]);
+
+    // Samples of other processes tell which slots were overwritten
+    let mut children = Vec::new();
+    let mut child_pids = Vec::new();
+    for _ in 0..3 {
+        let mut child = Command::new(target_process_bin())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdout = child.stdout.take().expect("Failed to get stdout");
+        let mut pid_line = String::new();
+        BufReader::new(stdout).read_line(&mut pid_line)?;
+        child_pids.push(pid_line.trim().to_string());
+        children.push(child);
+    }
+
+    // The capacity of an existing recording is kept
+    run_succeeds([
+        "record",
+        "--pid",
+        &child_pids[0],
+        "--file",
+        file,
+        "--capacity",
+        "10",
+        "--count",
+        "1",
+    ]);
+    assert_eq!(std::fs::metadata(&path)?.len(), 24 + 3 * 24);
+    assert_eq!(
+        pids(&replay()?),
+        [&own_pid, &own_pid, &child_pids[0]].map(String::as_str)
+    );
+
+    for pid in &child_pids[1..] {
+        run_succeeds(["record", "--pid", pid, "--file", file, "--count", "1"]);
+    }
+    let samples = replay()?;
+    assert_eq!(pids(&samples), child_pids);
+    assert!(
+        samples
+            .windows(2)
+            .all(|w| { w[0]["sampled_at"].as_str() <= w[1]["sampled_at"].as_str() })
+    );
+
+    let assert = run_succeeds(["replay", "--file", file, "--summary", "--format", "json"]);
+    let summary: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(summary["samples"], 3);
+    assert_eq!(summary["categories"][0]["category"], "total_descriptors");
+    assert!(summary["categories"][0]["max"].as_u64() >= summary["categories"][0]["min"].as_u64());
+
+    for mut child in children {
+        if let Some(mut stdin) = child.stdin.take() {
+            let _ = stdin.write_all(b"quit\n");
+        }
+        let _ = child.wait();
+    }
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn fail_with_a_zero_interval() -> Result<(), Box<dyn Error>> {
+    run_fails(["--pid", "1", "--interval", "0"])
+        .code(2)
+        .stderr(output_includes("must be longer than 0"));
+    Ok(())
+}
+
+#[test]
+fn fail_with_an_interval_that_overflows() -> Result<(), Box<dyn Error>> {
+    run_fails(["--pid", "1", "--interval", "99999999999999999h"])
+        .code(2)
+        .stderr(output_includes("too long"));
+    Ok(())
+}
+
+#[test]
+fn fail_to_record_with_a_zero_interval() -> Result<(), Box<dyn Error>> {
+    run_fails([
+        "record",
+        "--pid",
+        "1",
+        "--interval",
+        "0",
+        "--file",
+        "fshc.db",
+    ])
+    .code(2)
+    .stderr(output_includes("must be longer than 0"));
+    Ok(())
+}
+
+#[test]
+fn fail_to_replay_a_file_that_is_not_a_recording() -> Result<(), Box<dyn Error>> {
+    let path = std::env::temp_dir().join(format!("fshc_not_a_recording_{}", std::process::id()));
+    std::fs::write(&path, b"not a recording")?;
+
+    run_fails([
+        "replay".as_ref(),
+        "--file".as_ref(),
+        path.as_os_str(),
+        "--format".as_ref(),
+        "json".as_ref(),
+    ])
+    .code(65)
+    .stderr(output_includes("not an fshc recording"));
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn summarize_samples_instead_of_printing_them() -> Result<(), Box<dyn Error>> {
+    let own_pid = std::process::id().to_string();
+
+    let assert = run_succeeds([
+        "--pid",
+        &own_pid,
+        "--interval",
+        "10ms",
+        "--count",
+        "4",
+        "--summary",
+        "--format",
+        "json",
+    ]);
+    let output = String::from_utf8(assert.get_output().stdout.clone())?;
+    assert_eq!(output.lines().count(), 1);
+
+    let summary: serde_json::Value = serde_json::from_str(&output)?;
+    assert_eq!(summary["pid"].to_string(), own_pid);
+    assert_eq!(summary["samples"], 4);
+    let total = &summary["categories"][0];
+    assert_eq!(total["category"], "total_descriptors");
+    assert!(total["min"].as_u64() <= total["p50"].as_u64());
+    assert!(total["p50"].as_u64() <= total["p99"].as_u64());
+    assert!(total["p99"].as_u64() <= total["max"].as_u64());
+    assert!(total["peak_at"].is_string());
+    Ok(())
+}
+
+#[test]
+fn fail_with_summary_but_no_interval() -> Result<(), Box<dyn Error>> {
+    run_fails(["--pid", "1", "--summary"]).stderr(output_includes("--interval"));
+    run_fails([
+        "--pid",
+        "1",
+        "--interval",
+        "1s",
+        "--summary",
+        "--format",
+        "csv",
+    ])
+    .code(2)
+    .stderr(output_includes("--summary only supports"));
+    Ok(())
+}
+
+#[test]
+fn wait_until_a_condition_holds() -> Result<(), Box<dyn Error>> {
+    let own_pid = std::process::id().to_string();
+
+    let assert = run_succeeds([
+        "wait",
+        "--pid",
+        &own_pid,
+        "--until",
+        "total>=1",
+        "--until",
+        "total<1000000",
+        "--format",
+        "json",
+    ]);
+    let stats: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(stats["pid"].to_string(), own_pid);
+    Ok(())
+}
+
+#[test]
+fn fail_to_wait_for_a_condition_that_does_not_hold_in_time() -> Result<(), Box<dyn Error>> {
+    let own_pid = std::process::id().to_string();
+
+    let assert = run_fails([
+        "wait",
+        "--pid",
+        &own_pid,
+        "--until",
+        "total>1000000",
+        "--interval",
+        "50ms",
+        "--timeout",
+        "200ms",
+        "--format",
+        "json",
+    ])
+    .code(75);
+    let failure: serde_json::Value = serde_json::from_slice(&assert.get_output().stderr)?;
+    assert_eq!(failure["code"], "timed_out");
+    assert_eq!(
+        failure["message"],
+        format!("Process {own_pid} did not reach total>1000000 in time")
+    );
+    Ok(())
+}
+
+#[test]
+fn fail_to_wait_with_an_invalid_condition() -> Result<(), Box<dyn Error>> {
+    run_fails(["wait", "--pid", "1", "--until", "sockets"])
+        .code(2)
+        .stderr(output_includes("not a valid condition"));
+    run_fails(["wait", "--pid", "1", "--until", "connections<10"])
+        .code(2)
+        .stderr(output_includes("unknown category"));
+    Ok(())
+}
+
+#[test]
+fn fail_to_wait_with_a_zero_interval() -> Result<(), Box<dyn Error>> {
+    run_fails([
+        "wait",
+        "--pid",
+        "1",
+        "--until",
+        "total<1",
+        "--interval",
+        "0ms",
+    ])
+    .code(2)
+    .stderr(output_includes("must be longer than 0"));
+    Ok(())
+}
+
+#[test]
+fn fail_to_snapshot_pid_out_of_range() -> Result<(), Box<dyn Error>> {
+    for pid in ["0", "100000"] {
+        let assert = run_fails(["snapshot", "--pid", pid]).code(65);
+        let failure: serde_json::Value = serde_json::from_slice(&assert.get_output().stderr)?;
+        assert_eq!(failure["code"], "pid_out_of_range");
+    }
+    Ok(())
+}
+
+#[test]
+fn fail_to_diff_snapshots_of_another_version() -> Result<(), Box<dyn Error>> {
+    let path = std::env::temp_dir().join(format!("fshc_future_snapshot_{}", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"snapshot_version":2,"pid":1,"taken_at":"2026-01-01T00:00:00Z","descriptors":[]}"#,
+    )?;
+
+    run_fails([
+        "diff".as_ref(),
+        path.as_os_str(),
+        path.as_os_str(),
+        "--format".as_ref(),
+        "json".as_ref(),
+    ])
+    .code(65)
+    .stderr(output_includes("unsupported snapshot version 2"));
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
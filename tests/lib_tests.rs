@@ -0,0 +1,50 @@
+// Copyright (C) 2024-2025 Broadcom. All Rights Reserved.
+// The term "Broadcom" refers to Broadcom Inc. and/or its subsidiaries.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the library API, without going through the binary.
+
+use fshc::{FailureCode, FshcError, QueryOptions, count_descriptors};
+use std::error::Error;
+
+#[test]
+fn count_descriptors_of_current_process() -> Result<(), Box<dyn Error>> {
+    let stats = count_descriptors(std::process::id(), &QueryOptions::default())?;
+
+    assert_eq!(stats.pid, std::process::id());
+    assert!(stats.total_descriptors >= 1);
+    Ok(())
+}
+
+#[test]
+fn count_only_total_descriptors_of_current_process() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        only_total: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors(std::process::id(), &options)?;
+
+    assert!(stats.total_descriptors >= 1);
+    assert!(stats.socket_descriptors.is_none());
+    Ok(())
+}
+
+#[test]
+fn fail_to_count_descriptors_with_invalid_pid() {
+    let err = count_descriptors(0, &QueryOptions::default()).unwrap_err();
+
+    assert!(matches!(err, FshcError::PidOutOfRange));
+    assert_eq!(err.code(), FailureCode::PidOutOfRange);
+}
@@ -0,0 +1,383 @@
+// Copyright (C) 2024-2025 Broadcom. All Rights Reserved.
+// The term "Broadcom" refers to Broadcom Inc. and/or its subsidiaries.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Linux-specific CLI tests.
+//!
+//! These cover the reports that are built on top of procfs
+//! and are not available on other platforms.
+
+#![cfg(target_os = "linux")]
+
+mod test_helpers;
+
+use std::error::Error;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process::{Command, Stdio};
+use test_helpers::{output_includes, run_fails, run_succeeds, target_process_bin};
+
+#[test]
+fn query_target_process_with_unix_sockets_flag() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--unix-sockets"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: serde_json::Value = serde_json::from_str(&output)?;
+
+    let groups = json["unix_sockets"]
+        .as_array()
+        .expect("Output should include unix_sockets");
+    let expected_path = format!("fshc_test_{}.sock", target_pid);
+    let listener = groups
+        .iter()
+        .find(|g| {
+            g["path"]
+                .as_str()
+                .is_some_and(|p| p.ends_with(&expected_path))
+        })
+        .expect("Expected the Unix socket listener to be reported");
+    assert_eq!(listener["socket_type"], "stream");
+    assert_eq!(listener["count"], 1);
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn resolve_the_peers_of_unbound_unix_sockets() -> Result<(), Box<dyn Error>> {
+    let own_pid = std::process::id().to_string();
+    let path = std::env::temp_dir().join(format!("fshc_peer_{own_pid}.sock"));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path)?;
+    let _client = UnixStream::connect(&path)?;
+    let _server = listener.accept()?;
+
+    let assert = run_succeeds(["--pid", &own_pid, "--unix-sockets"]);
+    let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+
+    let groups = json["unix_sockets"]
+        .as_array()
+        .expect("Output should include unix_sockets");
+    let client = groups
+        .iter()
+        .find(|g| g["peer"].as_str() == path.to_str())
+        .expect("Expected the client end to be grouped by the path of its peer");
+    assert!(client["path"].is_null());
+    assert_eq!(client["socket_type"], "stream");
+
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn query_target_process_with_fd_flags_flag() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--fd-flags"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: serde_json::Value = serde_json::from_str(&output)?;
+
+    let flags = &json["fd_flags"];
+    let total = json["total_descriptors"].as_u64().unwrap_or(0);
+    let by_mode = flags["read_only"].as_u64().unwrap_or(0)
+        + flags["write_only"].as_u64().unwrap_or(0)
+        + flags["read_write"].as_u64().unwrap_or(0);
+    assert_eq!(
+        by_mode, total,
+        "Every descriptor should have an access mode"
+    );
+    // the Rust standard library opens files and sockets with O_CLOEXEC,
+    // only the inherited stdio descriptors lack it
+    assert!(flags["without_cloexec"].as_u64().unwrap_or(0) <= 3);
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn query_target_process_with_watches_flag() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--watches"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: serde_json::Value = serde_json::from_str(&output)?;
+
+    // the target process registers both of its sockets with one epoll
+    // instance and watches its file with one inotify instance
+    let watches = &json["watches"];
+    assert_eq!(watches["epoll_instances"], 1);
+    assert_eq!(watches["epoll_watches"], 2);
+    assert_eq!(watches["inotify_instances"], 1);
+    assert_eq!(watches["inotify_watches"], 1);
+    assert!(watches["max_user_inotify_watches"].as_u64().is_some());
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn query_target_process_with_locks_flag() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--locks"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: serde_json::Value = serde_json::from_str(&output)?;
+
+    let locks = json["locks"]
+        .as_array()
+        .expect("Output should include locks");
+    let expected_path = format!("fshc_test_{}.tmp", target_pid);
+    let lock = locks
+        .iter()
+        .find(|l| {
+            l["path"]
+                .as_str()
+                .is_some_and(|p| p.ends_with(&expected_path))
+        })
+        .expect("Expected the lock on the temp file to be reported");
+    assert_eq!(lock["lock_type"], "flock");
+    assert_eq!(lock["kind"], "write");
+    assert_eq!(lock["mode"], "advisory");
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn query_target_process_with_mappings_flag() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds(["--pid", target_pid, "--mappings"]);
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    let json: serde_json::Value = serde_json::from_str(&output)?;
+
+    let mappings = &json["mappings"];
+    let files = mappings["files"]
+        .as_array()
+        .expect("Output should include mapped files");
+    assert_eq!(mappings["mapped_files"].as_u64(), Some(files.len() as u64));
+    // the executable itself is mapped but has no open descriptor
+    let executable = files
+        .iter()
+        .find(|f| {
+            f["path"]
+                .as_str()
+                .is_some_and(|p| p.contains("target_process"))
+        })
+        .expect("Expected the executable to be mapped");
+    assert_eq!(executable["has_descriptor"], false);
+    assert!(mappings["without_descriptor"].as_u64().unwrap_or(0) >= 1);
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn only_total_matches_the_total_of_listing_by_type() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let total = |args: &[&str]| -> Result<serde_json::Value, Box<dyn Error>> {
+        let assert = run_succeeds([&["--pid", target_pid][..], args].concat());
+        let json: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+        Ok(json["total_descriptors"].clone())
+    };
+    let counted = total(&["--only-total"])?;
+    assert!(counted.as_u64().unwrap() >= 5);
+    assert_eq!(counted, total(&[])?);
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn snapshot_target_process_and_diff_snapshots() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let before_path = std::env::temp_dir().join(format!("fshc_before_{target_pid}.json"));
+    let after_path = std::env::temp_dir().join(format!("fshc_after_{target_pid}.json"));
+    run_succeeds([
+        "snapshot".as_ref(),
+        "--pid".as_ref(),
+        target_pid.as_ref(),
+        "-o".as_ref(),
+        before_path.as_os_str(),
+    ]);
+
+    let mut snapshot: serde_json::Value = serde_json::from_slice(&std::fs::read(&before_path)?)?;
+    let descriptors = snapshot["descriptors"]
+        .as_array_mut()
+        .expect("Expected descriptors");
+    assert!(descriptors.iter().any(|d| d["target"]["socket"].is_u64()));
+    descriptors.pop().expect("Expected at least one descriptor");
+    descriptors
+        .push(serde_json::json!({ "fd": 1000, "target": { "file": "/var/log/leaked.log" } }));
+    descriptors
+        .push(serde_json::json!({ "fd": 1001, "target": { "file": "/var/log/leaked.log" } }));
+    std::fs::write(&after_path, serde_json::to_vec(&snapshot)?)?;
+
+    let assert = run_succeeds([
+        "diff".as_ref(),
+        before_path.as_os_str(),
+        after_path.as_os_str(),
+        "--format".as_ref(),
+        "json".as_ref(),
+    ]);
+    let diff: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert_eq!(
+        diff["opened"],
+        serde_json::json!([{ "type": "file", "path": "/var/log/leaked.log", "count": 2 }])
+    );
+    assert_eq!(diff["closed"].as_array().map(Vec::len), Some(1));
+    assert_eq!(diff["closed"][0]["count"], 1);
+
+    let _ = std::fs::remove_file(&before_path);
+    let _ = std::fs::remove_file(&after_path);
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}
+
+#[test]
+fn report_descriptor_limit_only_when_it_is_used() -> Result<(), Box<dyn Error>> {
+    let own_pid = std::process::id().to_string();
+
+    let assert = run_succeeds(["--pid", &own_pid, "--format", "json"]);
+    let stats: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert!(stats.get("descriptor_limit").is_none());
+
+    let assert = run_succeeds(["--pid", &own_pid, "--limit", "--format", "json"]);
+    let stats: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert!(stats["descriptor_limit"]["soft"].as_u64().is_some());
+
+    run_succeeds(["--pid", &own_pid, "--format", "prometheus"])
+        .stdout(output_includes("fshc_descriptor_limit{"));
+    Ok(())
+}
+
+#[test]
+fn report_the_same_errno_for_a_missing_process_with_and_without_only_total()
+-> Result<(), Box<dyn Error>> {
+    // the pid of a process that has already exited and been reaped
+    let mut exited = Command::new(target_process_bin())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .spawn()?;
+    let exited_pid = exited.id().to_string();
+    exited.wait()?;
+
+    for only_total in [false, true] {
+        let mut args = vec!["--pid", &exited_pid, "--format", "json"];
+        if only_total {
+            args.push("--only-total");
+        }
+        let assert = run_fails(args).code(65);
+        let failure: serde_json::Value = serde_json::from_slice(&assert.get_output().stderr)?;
+        assert_eq!(failure["code"], "not_found");
+        assert_eq!(failure["pid"].to_string(), exited_pid);
+        // ENOENT
+        assert_eq!(failure["errno"], 2, "only_total: {only_total}");
+    }
+    Ok(())
+}
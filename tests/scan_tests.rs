@@ -0,0 +1,112 @@
+// Copyright (C) 2024-2025 Broadcom. All Rights Reserved.
+// The term "Broadcom" refers to Broadcom Inc. and/or its subsidiaries.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of scanning many processes on worker threads.
+
+use fshc::scan::Scanner;
+use fshc::source::{Descriptor, DescriptorSource, DescriptorTarget};
+use fshc::{FailureCode, FshcError, Pid, QueryOptions};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Every process has as many descriptors as its pid.
+/// Process 13 hangs, like a process in D state would.
+struct SlowSource;
+
+impl DescriptorSource for SlowSource {
+    fn descriptors(&self, pid: Pid) -> Result<Vec<Descriptor>, FshcError> {
+        if pid == 13 {
+            thread::sleep(Duration::from_secs(10));
+        }
+        Ok((0..pid as i32)
+            .map(|fd| Descriptor {
+                fd,
+                target: DescriptorTarget::Other,
+            })
+            .collect())
+    }
+}
+
+#[test]
+fn scan_returns_results_in_order() {
+    let scanner = Scanner::new(3, None);
+    let pids = [5, 1, 0, 8, 2];
+
+    let results = scanner.scan_with(Arc::new(SlowSource), &pids, &QueryOptions::default());
+
+    assert_eq!(results.len(), pids.len());
+    for (pid, result) in pids.iter().zip(&results) {
+        match result {
+            Ok(stats) => assert_eq!(stats.total_descriptors, *pid),
+            Err(err) => {
+                assert_eq!(*pid, 0);
+                assert_eq!(err.code(), FailureCode::PidOutOfRange);
+            }
+        }
+    }
+}
+
+#[test]
+fn scan_gives_up_on_processes_that_hang() {
+    let scanner = Scanner::new(2, Some(Duration::from_millis(200)));
+
+    let started = Instant::now();
+    let results = scanner.scan_with(
+        Arc::new(SlowSource),
+        &[13, 3, 4, 5],
+        &QueryOptions::default(),
+    );
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_eq!(
+        results[0].as_ref().unwrap_err().code(),
+        FailureCode::TimedOut
+    );
+    for result in &results[1..] {
+        assert!(result.is_ok());
+    }
+}
+
+/// Hangs on every query and counts them.
+#[derive(Default)]
+struct HangingSource {
+    queries: AtomicUsize,
+}
+
+impl DescriptorSource for HangingSource {
+    fn descriptors(&self, _pid: Pid) -> Result<Vec<Descriptor>, FshcError> {
+        self.queries.fetch_add(1, Ordering::SeqCst);
+        thread::sleep(Duration::from_secs(10));
+        Ok(Vec::new())
+    }
+}
+
+#[test]
+fn scan_does_not_query_a_hung_process_again() {
+    let scanner = Scanner::new(2, Some(Duration::from_millis(100)));
+    let source = Arc::new(HangingSource::default());
+
+    for _ in 0..3 {
+        let results = scanner.scan_with(source.clone(), &[13], &QueryOptions::default());
+        assert_eq!(
+            results[0].as_ref().unwrap_err().code(),
+            FailureCode::TimedOut
+        );
+    }
+
+    assert_eq!(source.queries.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,246 @@
+// Copyright (C) 2024-2025 Broadcom. All Rights Reserved.
+// The term "Broadcom" refers to Broadcom Inc. and/or its subsidiaries.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the aggregation logic against a fake descriptor source.
+
+use fshc::outcome::{FileLockKind, FileLockType, UnixSocketType};
+use fshc::source::{DescriptorSource, FakeSource};
+use fshc::{FailureCode, QueryOptions, count_descriptors_with};
+use std::error::Error;
+
+fn fixture() -> FakeSource {
+    FakeSource::from_json(include_str!("fixtures/processes.json")).expect("invalid fixture")
+}
+
+#[test]
+fn classify_descriptors_by_type() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        limit: true,
+        ..Default::default()
+    };
+    let stats = count_descriptors_with(&fixture(), 42, &options)?;
+
+    assert_eq!(stats.pid, 42);
+    assert_eq!(stats.total_descriptors, 10);
+    assert_eq!(stats.file_descriptors, Some(2));
+    assert_eq!(stats.socket_descriptors, Some(3));
+    let limit = stats.descriptor_limit.expect("missing limit");
+    assert_eq!(limit.soft, Some(1024));
+    assert_eq!(limit.hard, None);
+    Ok(())
+}
+
+#[test]
+fn count_only_total_descriptors() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        only_total: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors_with(&fixture(), 42, &options)?;
+
+    assert_eq!(stats.total_descriptors, 10);
+    assert_eq!(stats.file_descriptors, None);
+    assert_eq!(stats.socket_descriptors, None);
+    Ok(())
+}
+
+#[test]
+fn count_process_without_descriptors() -> Result<(), Box<dyn Error>> {
+    let source = fixture();
+    let stats = count_descriptors_with(&source, 43, &QueryOptions::default())?;
+
+    assert_eq!(stats.total_descriptors, 0);
+    assert_eq!(stats.file_descriptors, Some(0));
+    assert!(stats.descriptor_limit.is_none());
+    assert!(source.descriptors(43)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn fail_for_process_missing_from_fixture() {
+    let err = count_descriptors_with(&fixture(), 44, &QueryOptions::default()).unwrap_err();
+
+    assert_eq!(err.code(), FailureCode::NotFound);
+}
+
+#[test]
+fn group_unix_sockets_by_path_peer_and_type() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        unix_sockets: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors_with(&fixture(), 42, &options)?;
+
+    let groups: Vec<_> = stats
+        .unix_sockets
+        .expect("missing Unix sockets")
+        .into_iter()
+        .map(|group| (group.path, group.peer, group.socket_type, group.count))
+        .collect();
+    let path = |path: &str| Some(path.to_string());
+    assert_eq!(
+        groups,
+        [
+            // the client end of a connection to a helper process
+            (
+                None,
+                path("/run/rabbitmq-helper.sock"),
+                UnixSocketType::Stream,
+                1
+            ),
+            (path("/run/rabbitmq.sock"), None, UnixSocketType::Stream, 1),
+            (path("@erlang"), None, UnixSocketType::Dgram, 1),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn summarize_descriptor_flags() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        fd_flags: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors_with(&fixture(), 42, &options)?;
+
+    let flags = stats.fd_flags.expect("missing flags");
+    assert_eq!(flags.read_only, 2);
+    assert_eq!(flags.write_only, 2);
+    assert_eq!(flags.read_write, 5);
+    assert_eq!(flags.non_blocking, 4);
+    assert_eq!(flags.without_cloexec, 4);
+    Ok(())
+}
+
+#[test]
+fn count_epoll_and_inotify_watches() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        watches: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors_with(&fixture(), 42, &options)?;
+
+    let watches = stats.watches.expect("missing watches");
+    assert_eq!(watches.epoll_instances, 1);
+    assert_eq!(watches.epoll_watches, 2);
+    assert_eq!(watches.inotify_instances, 1);
+    assert_eq!(watches.inotify_watches, 1);
+    assert_eq!(watches.max_user_inotify_watches, Some(8192));
+    Ok(())
+}
+
+#[test]
+fn list_locks_held_on_open_files() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        locks: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors_with(&fixture(), 42, &options)?;
+
+    let locks: Vec<_> = stats
+        .locks
+        .expect("missing locks")
+        .into_iter()
+        .map(|lock| (lock.path, lock.lock_type, lock.kind))
+        .collect();
+    let rdq = "/var/lib/rabbitmq/mnesia/rabbit@node/msg_stores/0.rdq".to_string();
+    assert_eq!(
+        locks,
+        [
+            // inherited across fork(), so its pid is the one of the parent
+            (rdq.clone(), FileLockType::Flock, FileLockKind::Write),
+            (rdq.clone(), FileLockType::Posix, FileLockKind::Write),
+            (rdq, FileLockType::Ofd, FileLockKind::Read),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn list_locks_of_duplicated_descriptors_once() -> Result<(), Box<dyn Error>> {
+    let fdinfo = "pos:\t0\nflags:\t02\nlock:\t1: FLOCK  ADVISORY  WRITE 7 00:2a:12 0 EOF\n";
+    let fixture = serde_json::json!({
+        "processes": {
+            "7": {
+                "descriptors": [
+                    { "fd": 3, "target": { "file": "/run/app.lock" } },
+                    { "fd": 4, "target": { "file": "/run/app.lock" } }
+                ],
+                "fdinfo": { "3": fdinfo, "4": fdinfo }
+            }
+        }
+    });
+    let source = FakeSource::from_json(&fixture.to_string())?;
+    let options = QueryOptions {
+        locks: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors_with(&source, 7, &options)?;
+
+    assert_eq!(stats.locks.map(|locks| locks.len()), Some(1));
+    Ok(())
+}
+
+#[test]
+fn list_mapped_files() -> Result<(), Box<dyn Error>> {
+    let options = QueryOptions {
+        mappings: true,
+        ..Default::default()
+    };
+
+    let stats = count_descriptors_with(&fixture(), 42, &options)?;
+
+    let mappings = stats.mappings.expect("missing mappings");
+    assert_eq!(mappings.mapped_files, 3);
+    assert_eq!(mappings.without_descriptor, 2);
+    assert_eq!(mappings.map_files_entries, Some(4));
+    let files: Vec<_> = mappings
+        .files
+        .iter()
+        .map(|f| (f.path.as_str(), f.mappings, f.has_descriptor, f.deleted))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            ("/memfd:buffer (deleted)", 1, false, true),
+            ("/usr/lib/x86_64-linux-gnu/libc.so.6", 2, false, false),
+            (
+                "/var/lib/rabbitmq/mnesia/rabbit@node/msg_stores/0.rdq",
+                1,
+                true,
+                false
+            ),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn fail_for_report_the_source_does_not_support() {
+    let options = QueryOptions {
+        unix_sockets: true,
+        ..Default::default()
+    };
+
+    let err = count_descriptors_with(&fixture(), 43, &options).unwrap_err();
+
+    assert_eq!(err.code(), FailureCode::Unsupported);
+}
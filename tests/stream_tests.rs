@@ -0,0 +1,55 @@
+// Copyright (C) 2024-2025 Broadcom. All Rights Reserved.
+// The term "Broadcom" refers to Broadcom Inc. and/or its subsidiaries.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests of the async API.
+
+#![cfg(feature = "async")]
+
+use fshc::{FailureCode, QueryOptions};
+use futures_executor::block_on;
+use futures_util::StreamExt;
+
+#[test]
+fn count_descriptors_of_many_processes() {
+    let own_pid = std::process::id();
+    let pids = [own_pid, 0, own_pid, own_pid];
+
+    let mut results: Vec<_> =
+        block_on(fshc::stream::count_descriptors_many(pids, QueryOptions::default(), 2).collect());
+    results.sort_by_key(|(pid, _)| *pid);
+
+    assert_eq!(results.len(), 4);
+    let (pid, failed) = &results[0];
+    assert_eq!(*pid, 0);
+    assert_eq!(
+        failed.as_ref().unwrap_err().code(),
+        FailureCode::PidOutOfRange
+    );
+    for (pid, result) in &results[1..] {
+        assert_eq!(*pid, own_pid);
+        assert!(result.as_ref().unwrap().total_descriptors >= 1);
+    }
+}
+
+#[test]
+fn count_descriptors_without_concurrency() {
+    let results: Vec<_> = block_on(
+        fshc::stream::count_descriptors_many([std::process::id()], QueryOptions::default(), 0)
+            .collect(),
+    );
+
+    assert_eq!(results.len(), 1);
+    assert!(results[0].1.is_ok());
+}
@@ -48,3 +48,8 @@ pub fn output_includes(content: &str) -> ContainsPredicate {
 pub fn target_process_bin() -> PathBuf {
     cargo_bin("target_process")
 }
+
+#[allow(deprecated)]
+pub fn fshc_bin() -> PathBuf {
+    cargo_bin("fshc")
+}
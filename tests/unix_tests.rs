@@ -86,3 +86,36 @@ fn query_target_process_has_expected_minimum_sockets() -> Result<(), Box<dyn Err
 
     Ok(())
 }
+
+#[test]
+fn wait_until_a_socket_condition_holds() -> Result<(), Box<dyn Error>> {
+    let mut child = Command::new(target_process_bin())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("Failed to get stdout");
+    let mut reader = BufReader::new(stdout);
+    let mut pid_line = String::new();
+    reader.read_line(&mut pid_line)?;
+    let target_pid = pid_line.trim();
+
+    let assert = run_succeeds([
+        "wait",
+        "--pid",
+        target_pid,
+        "--until",
+        "sockets>=2",
+        "--format",
+        "json",
+    ]);
+    let stats: serde_json::Value = serde_json::from_slice(&assert.get_output().stdout)?;
+    assert!(stats["socket_descriptors"].as_u64() >= Some(2));
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(b"quit\n");
+    }
+    let _ = child.wait();
+
+    Ok(())
+}